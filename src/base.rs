@@ -1,11 +1,11 @@
 //! Provides access to player bases.
 
-use crate::spirit::LivingSpirit;
 use crate::{
-    prelude::*, CanFrom, Destructible, DestructibleID, LivingDestructible, LivingDestructibleID,
+    prelude::*, require_property, require_structure_type, CanFrom, ConversionErrorKind,
+    Destructible, DestructibleID, LivingDestructible, LivingDestructibleID,
 };
-use crate::{Structure, StructureID};
-use js_sys::{Object, Reflect};
+use crate::{Structure, StructureID, StructureType};
+use js_sys::Object;
 use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
 
@@ -33,30 +33,30 @@ extern "C" {
 
 impl CanFrom<Structure> for Base {
     #[inline]
-    fn can_from(value: &Structure) -> bool {
-        value.structure_type().to_str() == "base"
+    fn check(value: &Structure) -> Result<(), ConversionErrorKind> {
+        require_structure_type(value, StructureType::Base)
     }
 }
 
-try_can_from!(impl TryFrom<Structure>, Error = Structure for Base);
+try_can_from!(impl TryFrom<Structure> for Base);
 
 impl CanFrom<Destructible> for Base {
     #[inline]
-    fn can_from(value: &Destructible) -> bool {
-        Reflect::has(value, &"current_spirit_cost".into()).unwrap()
+    fn check(value: &Destructible) -> Result<(), ConversionErrorKind> {
+        require_property(value, "current_spirit_cost")
     }
 }
 
-try_can_from!(impl TryFrom<Destructible>, Error = Spirit for Base);
+try_can_from!(impl TryFrom<Destructible> for Base);
 
 impl CanFrom<LivingDestructible> for Base {
     #[inline]
-    fn can_from(value: &LivingDestructible) -> bool {
-        <Base as CanFrom<Destructible>>::can_from(value)
+    fn check(value: &LivingDestructible) -> Result<(), ConversionErrorKind> {
+        <Base as CanFrom<Destructible>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<LivingDestructible>, Error = LivingSpirit for Base);
+try_can_from!(impl TryFrom<LivingDestructible> for Base);
 
 // `bases`
 #[wasm_bindgen]
@@ -0,0 +1,281 @@
+//! Keeps track of how much of the tick's execution time is left.
+//!
+//! Call [`start_tick`] first thing in your tick, and [`end_tick`] last thing. In between, [`elapsed`] and
+//! [`remaining`] tell you where you stand, and a [`Deadline`] lets expensive computations stop early.
+//! Tick durations are recorded in the [`history`], which is saved to `memory` every [`SAVE_INTERVAL`] ticks, or
+//! whenever you call [`save_history`].
+
+use crate::memory::{MemoryError, MemoryKey};
+use crate::now;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+};
+
+/// The default time budget for a tick, in milliseconds. Change it with [`set_budget`].
+pub const DEFAULT_BUDGET: f64 = 50.0;
+
+/// How many recent ticks [`TickHistory`] keeps.
+pub const HISTORY_LEN: usize = 100;
+
+/// How often [`end_tick`] saves the [`history`] to `memory`, in ticks.
+pub const SAVE_INTERVAL: u32 = 10;
+
+const HISTORY: MemoryKey<TickHistory> = MemoryKey::new("__tick_history");
+
+thread_local! {
+    static START: Cell<Option<f64>> = const { Cell::new(None) };
+    static BUDGET: Cell<f64> = const { Cell::new(DEFAULT_BUDGET) };
+    /// The history, loaded from `memory` when first needed.
+    static LOADED_HISTORY: RefCell<Option<TickHistory>> = const { RefCell::new(None) };
+}
+
+/// Calls `f` with the history, loading it from `memory` first if needed.
+fn with_history<R>(f: impl FnOnce(&mut TickHistory) -> R) -> Result<R, MemoryError> {
+    LOADED_HISTORY.with(|loaded| {
+        let mut loaded = loaded.borrow_mut();
+        let history = match &mut *loaded {
+            Some(history) => history,
+            None => loaded.insert(HISTORY.get_or_default()?),
+        };
+        Ok(f(history))
+    })
+}
+
+/// Records the start of the tick.
+#[inline]
+pub fn start_tick() {
+    START.with(|start| start.set(Some(now())));
+}
+
+/// Records the end of the tick in the [`history`], and returns how long the tick took, in milliseconds.
+/// Every [`SAVE_INTERVAL`] ticks, also saves the history to `memory`.
+pub fn end_tick() -> Result<f64, MemoryError> {
+    let duration = elapsed();
+    let ticks = with_history(|history| {
+        history.push(crate::current_tick(), duration);
+        history.ticks
+    })?;
+    START.with(|start| start.set(None));
+    if ticks % SAVE_INTERVAL == 0 {
+        save_history()?;
+    }
+    Ok(duration)
+}
+
+/// Sets the time budget for each tick, in milliseconds.
+#[inline]
+pub fn set_budget(budget: f64) {
+    BUDGET.with(|b| b.set(budget));
+}
+
+/// The time budget for each tick, in milliseconds.
+#[inline]
+pub fn budget() -> f64 {
+    BUDGET.with(Cell::get)
+}
+
+/// Milliseconds since [`start_tick`], or 0 if it wasn't called.
+#[inline]
+pub fn elapsed() -> f64 {
+    START.with(Cell::get).map_or(0.0, |start| now() - start)
+}
+
+/// Milliseconds left in the tick's [`budget`]. Never negative.
+#[inline]
+pub fn remaining() -> f64 {
+    (budget() - elapsed()).max(0.0)
+}
+
+/// A point in time that long-running computations can poll, to return their best result so far when it's reached.
+///
+/// ```ignore
+/// let deadline = Deadline::fraction_of_remaining(0.5);
+/// let mut plan = Plan::greedy();
+/// deadline.run(|| plan.refine());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Deadline {
+    at: f64,
+}
+
+impl Deadline {
+    /// A deadline `ms` milliseconds from now.
+    #[inline]
+    pub fn after(ms: f64) -> Self {
+        Deadline { at: now() + ms }
+    }
+
+    /// A deadline `margin` milliseconds before the end of the tick's [`budget`].
+    #[inline]
+    pub fn tick_end(margin: f64) -> Self {
+        Deadline::after(remaining() - margin)
+    }
+
+    /// A deadline after `fraction` of the time [`remaining`] in the tick.
+    #[inline]
+    pub fn fraction_of_remaining(fraction: f64) -> Self {
+        Deadline::after(remaining() * fraction)
+    }
+
+    /// Milliseconds until the deadline. Never negative.
+    #[inline]
+    pub fn remaining(&self) -> f64 {
+        (self.at - now()).max(0.0)
+    }
+
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        now() >= self.at
+    }
+
+    /// Calls `step` until it returns `false` or the deadline expires, and returns how many times it was called.
+    /// `step` is always called at least once, so there is a result to fall back on.
+    pub fn run(&self, mut step: impl FnMut() -> bool) -> u32 {
+        let mut steps = 0;
+        loop {
+            steps += 1;
+            if !step() || self.is_expired() {
+                return steps;
+            }
+        }
+    }
+}
+
+/// How long a tick took.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TickTime {
+    pub tick: u32,
+    /// In milliseconds.
+    pub duration: f64,
+}
+
+/// Durations of past ticks, stored in `memory`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickHistory {
+    /// The last [`HISTORY_LEN`] ticks, oldest first.
+    pub recent: VecDeque<TickTime>,
+    /// The number of ticks recorded over the whole game.
+    pub ticks: u32,
+    /// The total duration of those ticks, in milliseconds.
+    pub total: f64,
+    /// The longest tick of the game.
+    pub longest: Option<TickTime>,
+}
+
+impl TickHistory {
+    /// Records a tick.
+    pub fn push(&mut self, tick: u32, duration: f64) {
+        let time = TickTime { tick, duration };
+        if self.recent.len() == HISTORY_LEN {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(time);
+        self.ticks += 1;
+        self.total += duration;
+        if !matches!(self.longest, Some(longest) if longest.duration >= duration) {
+            self.longest = Some(time);
+        }
+    }
+
+    /// The average duration of a tick over the whole game, in milliseconds.
+    pub fn average(&self) -> f64 {
+        if self.ticks == 0 {
+            0.0
+        } else {
+            self.total / f64::from(self.ticks)
+        }
+    }
+
+    /// The duration that `percentile` percent of the [`recent`](TickHistory::recent) ticks took at most, in
+    /// milliseconds, by the nearest-rank method. 0 if there are no recent ticks.
+    pub fn recent_percentile(&self, percentile: f64) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let mut durations: Vec<f64> = self.recent.iter().map(|time| time.duration).collect();
+        durations.sort_by(f64::total_cmp);
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * durations.len() as f64).ceil() as usize;
+        durations[rank.saturating_sub(1)]
+    }
+
+    /// The average duration of the [`recent`](TickHistory::recent) ticks, in milliseconds.
+    pub fn recent_average(&self) -> f64 {
+        if self.recent.is_empty() {
+            0.0
+        } else {
+            self.recent.iter().map(|time| time.duration).sum::<f64>() / self.recent.len() as f64
+        }
+    }
+}
+
+/// The durations of past ticks, as recorded by [`end_tick`].
+pub fn history() -> Result<TickHistory, MemoryError> {
+    with_history(|history| history.clone())
+}
+
+/// Saves the [`history`] to `memory` now, rather than waiting for [`end_tick`] to.
+pub fn save_history() -> Result<(), MemoryError> {
+    with_history(|history| HISTORY.set(history))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_evicts_the_oldest_recent_tick_but_keeps_the_totals() {
+        let mut history = TickHistory::default();
+        for tick in 0..HISTORY_LEN as u32 + 5 {
+            history.push(tick, 2.0);
+        }
+        assert_eq!(history.recent.len(), HISTORY_LEN);
+        assert_eq!(history.recent.front().unwrap().tick, 5);
+        assert_eq!(history.recent.back().unwrap().tick, HISTORY_LEN as u32 + 4);
+        assert_eq!(history.ticks, HISTORY_LEN as u32 + 5);
+        assert_eq!(history.average(), 2.0);
+        assert_eq!(history.recent_average(), 2.0);
+    }
+
+    #[test]
+    fn longest_survives_eviction_and_keeps_the_first_of_ties() {
+        let mut history = TickHistory::default();
+        history.push(0, 1.0);
+        history.push(1, 9.0);
+        history.push(2, 9.0);
+        for tick in 3..HISTORY_LEN as u32 + 3 {
+            history.push(tick, 1.0);
+        }
+        assert!(history.recent.iter().all(|time| time.duration == 1.0));
+        assert_eq!(
+            history.longest,
+            Some(TickTime {
+                tick: 1,
+                duration: 9.0
+            })
+        );
+    }
+
+    #[test]
+    fn recent_percentile_uses_the_nearest_rank() {
+        let mut history = TickHistory::default();
+        assert_eq!(history.recent_percentile(50.0), 0.0);
+        // Out of order, to check that they're sorted.
+        for duration in (1..=HISTORY_LEN).rev() {
+            history.push(0, duration as f64);
+        }
+        assert_eq!(history.recent_percentile(0.0), 1.0);
+        assert_eq!(history.recent_percentile(50.0), 50.0);
+        assert_eq!(history.recent_percentile(90.5), 91.0);
+        assert_eq!(history.recent_percentile(100.0), HISTORY_LEN as f64);
+        assert_eq!(history.recent_percentile(150.0), HISTORY_LEN as f64);
+    }
+
+    #[test]
+    fn empty_history_averages_zero() {
+        let history = TickHistory::default();
+        assert_eq!(history.average(), 0.0);
+        assert_eq!(history.recent_average(), 0.0);
+    }
+}
@@ -0,0 +1,304 @@
+//! Opt-in memoization of entity getters, invalidated when the [`tick`](crate::current_tick) changes.
+//!
+//! Wrap an entity in [`Cached`] and read it through the wrapper: the first read of each property in a tick
+//! calls into JS, and later reads of the same property of the same entity (through any [`Cached`] wrapper) come
+//! from the thread's [`EntityCache`] without calling into JS at all. Entity properties don't change during a
+//! tick, since commands only take effect once it ends, so the cache is never stale as long as
+//! [`begin_tick`] is called at the start of each tick. Everything else is still reachable through [`Deref`].
+//!
+//! ```ignore
+//! cache::begin_tick();
+//! let mine: Vec<_> = spirits.operable().map(Cached::new).collect();
+//! for spirit in &mine {
+//!     // Each spirit's position is only read from JS once, however many times it's compared.
+//!     let neighbours = mine
+//!         .iter()
+//!         .filter(|other| other.position().distance(spirit.position()) < 50.0)
+//!         .count();
+//! }
+//! ```
+
+use crate::base::Base;
+use crate::spirit::{
+    DeadEnemySpirit, DeadFriendlySpirit, DeadSpirit, EnemySpirit, FriendlySpirit,
+    LivingEnemySpirit, LivingSpirit, OperableSpirit, Spirit,
+};
+use crate::{Destructible, Entity, LivingDestructible, Position, Shape};
+use std::{cell::RefCell, collections::HashMap, ops::Deref};
+use wasm_bindgen::JsCast;
+
+/// The properties of an entity that have been read this tick.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CachedFields {
+    pub position: Option<Position>,
+    pub size: Option<u32>,
+    pub energy: Option<i32>,
+    pub energy_capacity: Option<i32>,
+    pub hp: Option<i32>,
+    pub player_id: Option<String>,
+    pub shape: Option<Shape>,
+}
+
+/// Property values of entities, by entity ID, for the current tick.
+///
+/// [`Cached`] uses a thread-local instance; see [`with_cache`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityCache {
+    tick: Option<u32>,
+    entries: HashMap<String, CachedFields>,
+}
+
+impl EntityCache {
+    #[inline]
+    pub fn new() -> Self {
+        EntityCache::default()
+    }
+
+    /// Empties the cache if it holds properties from a tick other than `tick`.
+    pub fn begin_tick(&mut self, tick: u32) {
+        if self.tick != Some(tick) {
+            self.entries.clear();
+            self.tick = Some(tick);
+        }
+    }
+
+    /// The tick passed to the last [`begin_tick`](EntityCache::begin_tick), if any.
+    #[inline]
+    pub fn tick(&self) -> Option<u32> {
+        self.tick
+    }
+
+    /// The cached properties of the entity with ID `id`.
+    pub fn fields(&mut self, id: &str) -> &mut CachedFields {
+        if !self.entries.contains_key(id) {
+            self.entries.insert(id.to_owned(), CachedFields::default());
+        }
+        self.entries.get_mut(id).unwrap()
+    }
+
+    /// Forgets the cached properties of one entity.
+    #[inline]
+    pub fn invalidate(&mut self, id: &str) {
+        self.entries.remove(id);
+    }
+
+    /// Forgets everything.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The number of entities with cached properties.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<EntityCache> = RefCell::new(EntityCache::new());
+}
+
+/// Calls `f` with the cache used by [`Cached`].
+#[inline]
+pub fn with_cache<R>(f: impl FnOnce(&mut EntityCache) -> R) -> R {
+    CACHE.with(|cache| f(&mut cache.borrow_mut()))
+}
+
+/// Reads the [current tick](crate::current_tick) and passes it to the [`begin_tick`](EntityCache::begin_tick)
+/// of the cache used by [`Cached`]. Call it first thing in each tick.
+#[inline]
+pub fn begin_tick() {
+    let tick = crate::current_tick();
+    with_cache(|cache| cache.begin_tick(tick));
+}
+
+/// Entity types with the properties of a [`Destructible`].
+pub trait AsDestructible: JsCast {
+    #[inline]
+    fn as_destructible(&self) -> &Destructible {
+        self.unchecked_ref()
+    }
+}
+
+impl AsDestructible for Destructible {}
+impl AsDestructible for LivingDestructible {}
+impl AsDestructible for Base {}
+impl AsDestructible for Spirit {}
+impl AsDestructible for FriendlySpirit {}
+impl AsDestructible for EnemySpirit {}
+impl AsDestructible for LivingSpirit {}
+impl AsDestructible for DeadSpirit {}
+impl AsDestructible for OperableSpirit {}
+impl AsDestructible for DeadFriendlySpirit {}
+impl AsDestructible for LivingEnemySpirit {}
+impl AsDestructible for DeadEnemySpirit {}
+
+/// An entity whose property reads are memoized for the current tick.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cached<T: JsCast> {
+    id: String,
+    entity: T,
+}
+
+impl<T: JsCast> Cached<T> {
+    /// Wraps `entity`. Reads its ID right away.
+    #[inline]
+    pub fn new(entity: T) -> Self {
+        let id = entity
+            .unchecked_ref::<Entity>()
+            .id()
+            .as_string()
+            .unwrap_or_default();
+        Cached { id, entity }
+    }
+
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.entity
+    }
+
+    /// Returns the cached value of a field, or reads it with `read` and caches it.
+    fn get<F: Clone>(
+        &self,
+        field: impl Fn(&mut CachedFields) -> &mut Option<F>,
+        read: impl FnOnce(&T) -> F,
+    ) -> F {
+        with_cache(|cache| {
+            field(cache.fields(&self.id))
+                .get_or_insert_with(|| read(&self.entity))
+                .clone()
+        })
+    }
+
+    #[inline]
+    pub fn position(&self) -> Position {
+        self.get(
+            |fields| &mut fields.position,
+            |entity| entity.unchecked_ref::<Entity>().position(),
+        )
+    }
+
+    #[inline]
+    pub fn size(&self) -> u32 {
+        self.get(
+            |fields| &mut fields.size,
+            |entity| entity.unchecked_ref::<Entity>().size(),
+        )
+    }
+
+    #[inline]
+    pub fn energy(&self) -> i32 {
+        self.get(
+            |fields| &mut fields.energy,
+            |entity| entity.unchecked_ref::<Entity>().energy(),
+        )
+    }
+
+    #[inline]
+    pub fn energy_capacity(&self) -> i32 {
+        self.get(
+            |fields| &mut fields.energy_capacity,
+            |entity| entity.unchecked_ref::<Entity>().energy_capacity(),
+        )
+    }
+}
+
+impl<T: AsDestructible> Cached<T> {
+    #[inline]
+    pub fn hp(&self) -> i32 {
+        self.get(
+            |fields| &mut fields.hp,
+            |entity| entity.as_destructible().hp(),
+        )
+    }
+
+    #[inline]
+    pub fn player_id(&self) -> String {
+        self.get(
+            |fields| &mut fields.player_id,
+            |entity| {
+                entity
+                    .as_destructible()
+                    .player_id()
+                    .as_string()
+                    .unwrap_or_default()
+            },
+        )
+    }
+
+    #[inline]
+    pub fn shape(&self) -> Shape {
+        self.get(
+            |fields| &mut fields.shape,
+            |entity| entity.as_destructible().shape(),
+        )
+    }
+}
+
+impl<T: JsCast> Deref for Cached<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.entity
+    }
+}
+
+impl<T: JsCast> From<T> for Cached<T> {
+    #[inline]
+    fn from(entity: T) -> Self {
+        Cached::new(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use wasm_bindgen::JsValue;
+
+    #[test]
+    fn begin_tick_only_empties_the_cache_on_a_new_tick() {
+        let mut cache = EntityCache::new();
+        cache.begin_tick(1);
+        cache.fields("p1_1").energy = Some(5);
+        cache.begin_tick(1);
+        assert_eq!(cache.fields("p1_1").energy, Some(5));
+        cache.begin_tick(2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.tick(), Some(2));
+    }
+
+    #[test]
+    fn a_hit_does_not_read_the_value_again() {
+        let cached = Cached {
+            id: "p1_1".to_owned(),
+            entity: JsValue::UNDEFINED,
+        };
+        let reads = Cell::new(0);
+        let read = |_: &JsValue| {
+            reads.set(reads.get() + 1);
+            reads.get()
+        };
+
+        with_cache(|cache| cache.begin_tick(1));
+        assert_eq!(cached.get(|fields| &mut fields.energy, read), 1);
+        assert_eq!(cached.get(|fields| &mut fields.energy, read), 1);
+        assert_eq!(reads.get(), 1);
+
+        with_cache(|cache| cache.begin_tick(2));
+        assert_eq!(cached.get(|fields| &mut fields.energy, read), 2);
+        assert_eq!(reads.get(), 2);
+    }
+}
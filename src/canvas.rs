@@ -0,0 +1,374 @@
+//! A retained-mode drawing layer on top of [`graphics`](crate::graphics) or any other [`DebugDraw`] sink.
+//!
+//! Shapes are added to named [`Layer`]s of a [`Canvas`], and stay there until the layer is cleared.
+//! [`Canvas::flush`] draws every visible layer with [`GraphicsSink`], which only changes the style and line width
+//! when they differ from the previous shape's.
+//!
+//! ```ignore
+//! let mut canvas = Canvas::new();
+//! canvas.define_color("friendly", "#3ad");
+//! canvas
+//!     .layer("intent")
+//!     .clear()
+//!     .with_style(Style::stroke("friendly", 2.0))
+//!     .arrow(spirit.position(), target.position())
+//!     .range_ring(spirit.position(), 200.0);
+//! canvas.flush();
+//! ```
+
+use crate::color::ToCss;
+use crate::debug_draw::{DebugDraw, GraphicsSink};
+use crate::Position;
+use std::{collections::HashMap, f64::consts::TAU};
+
+/// How shapes are drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    /// A color name defined with [`Canvas::define_color`], or any CSS color.
+    /// [`stroke`](Style::stroke) and [`fill`](Style::fill) also accept [`Color`](crate::color::Color)s.
+    pub color: String,
+    pub width: f64,
+    /// Whether closed shapes are filled in. `graphics` can only draw outlines, so fills are hatched with
+    /// lines `width` apart.
+    pub fill: bool,
+}
+
+impl Style {
+    /// Outlines, `width` wide.
+    #[inline]
+    pub fn stroke(color: impl ToCss, width: f64) -> Self {
+        Style {
+            color: color.to_css().into_owned(),
+            width,
+            fill: false,
+        }
+    }
+
+    /// Filled shapes.
+    #[inline]
+    pub fn fill(color: impl ToCss) -> Self {
+        Style {
+            color: color.to_css().into_owned(),
+            width: 4.0,
+            fill: true,
+        }
+    }
+}
+
+impl Default for Style {
+    #[inline]
+    fn default() -> Self {
+        Style::stroke("white", 1.0)
+    }
+}
+
+/// The shapes all others are made of.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Primitive {
+    Line {
+        from: Position,
+        to: Position,
+    },
+    Circle {
+        center: Position,
+        radius: f64,
+    },
+    Rect {
+        top_left: Position,
+        bottom_right: Position,
+    },
+    /// Not supported by `graphics`, where it's skipped.
+    Text {
+        pos: Position,
+        text: String,
+    },
+}
+
+/// A named group of shapes that can be shown, hidden and cleared together.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layer {
+    visible: bool,
+    style: Style,
+    shapes: Vec<(Style, Primitive)>,
+}
+
+impl Layer {
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    #[inline]
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Removes all shapes from the layer.
+    #[inline]
+    pub fn clear(&mut self) -> &mut Self {
+        self.shapes.clear();
+        self
+    }
+
+    /// The shapes in the layer, in drawing order.
+    #[inline]
+    pub fn shapes(&self) -> &[(Style, Primitive)] {
+        &self.shapes
+    }
+
+    /// Sets the style of the shapes added after this.
+    #[inline]
+    pub fn with_style(&mut self, style: Style) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// Adds a primitive with the current style.
+    #[inline]
+    pub fn push(&mut self, primitive: Primitive) -> &mut Self {
+        self.shapes.push((self.style.clone(), primitive));
+        self
+    }
+
+    pub fn line(&mut self, from: Position, to: Position) -> &mut Self {
+        self.push(Primitive::Line { from, to })
+    }
+
+    pub fn circle(&mut self, center: Position, radius: f64) -> &mut Self {
+        if self.style.fill {
+            // Concentric rings, from the outside in.
+            let step = self.style.width.max(1.0);
+            let mut r = radius;
+            while r > 0.0 {
+                self.push(Primitive::Circle { center, radius: r });
+                r -= step;
+            }
+            self
+        } else {
+            self.push(Primitive::Circle { center, radius })
+        }
+    }
+
+    pub fn rect(&mut self, top_left: Position, bottom_right: Position) -> &mut Self {
+        if self.style.fill {
+            let top_right = Position::new(bottom_right.x, top_left.y);
+            let bottom_left = Position::new(top_left.x, bottom_right.y);
+            self.polygon(&[top_left, top_right, bottom_right, bottom_left])
+        } else {
+            self.push(Primitive::Rect {
+                top_left,
+                bottom_right,
+            })
+        }
+    }
+
+    pub fn text(&mut self, pos: Position, text: &str) -> &mut Self {
+        self.push(Primitive::Text {
+            pos,
+            text: text.to_owned(),
+        })
+    }
+
+    /// Lines through `points`, in order.
+    pub fn polyline(&mut self, points: &[Position]) -> &mut Self {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1]);
+        }
+        self
+    }
+
+    /// A closed polygon. Filled with horizontal lines if the style is [`fill`](Style::fill).
+    pub fn polygon(&mut self, points: &[Position]) -> &mut Self {
+        if points.len() < 2 {
+            return self;
+        }
+        if !self.style.fill {
+            self.polyline(points);
+            return self.line(points[points.len() - 1], points[0]);
+        }
+        let step = self.style.width.max(1.0);
+        let min = points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        let max = points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+        let mut y = min + step / 2.0;
+        while y < max {
+            // Even-odd scanline fill.
+            let mut crossings: Vec<f64> = points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+                .map(|(a, b)| a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x))
+                .collect();
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for span in crossings.chunks_exact(2) {
+                self.line(Position::new(span[0], y), Position::new(span[1], y));
+            }
+            y += step;
+        }
+        self
+    }
+
+    /// A line with an arrowhead at `to`.
+    pub fn arrow(&mut self, from: Position, to: Position) -> &mut Self {
+        const HEAD_LENGTH: f64 = 12.0;
+        const HEAD_ANGLE: f64 = 0.5;
+        self.line(from, to);
+        let back = (from - to).normalize() * HEAD_LENGTH;
+        if back == Position::ZERO {
+            return self;
+        }
+        self.line(to, to + back.rotate(HEAD_ANGLE));
+        self.line(to, to + back.rotate(-HEAD_ANGLE))
+    }
+
+    /// A dashed circle, for showing ranges without hiding what's inside.
+    pub fn range_ring(&mut self, center: Position, radius: f64) -> &mut Self {
+        const DASHES: u32 = 24;
+        for i in 0..DASHES {
+            let start = TAU * f64::from(i) / f64::from(DASHES);
+            let end = start + TAU / f64::from(DASHES) / 2.0;
+            self.line(
+                center + Position::from_angle(start) * radius,
+                center + Position::from_angle(end) * radius,
+            );
+        }
+        self
+    }
+
+    /// A crosshair at `pos`, with a label next to it.
+    pub fn marker(&mut self, pos: Position, label: &str) -> &mut Self {
+        const SIZE: f64 = 8.0;
+        self.line(
+            pos - Position::new(SIZE, 0.0),
+            pos + Position::new(SIZE, 0.0),
+        );
+        self.line(
+            pos - Position::new(0.0, SIZE),
+            pos + Position::new(0.0, SIZE),
+        );
+        self.push(Primitive::Circle {
+            center: pos,
+            radius: SIZE / 2.0,
+        });
+        if label.is_empty() {
+            self
+        } else {
+            self.text(pos + Position::new(SIZE, -SIZE), label)
+        }
+    }
+
+    /// A grid of `columns` by `rows` cells of size `cell`, with its top left corner at `origin`.
+    pub fn grid(&mut self, origin: Position, cell: f64, columns: u32, rows: u32) -> &mut Self {
+        let width = cell * f64::from(columns);
+        let height = cell * f64::from(rows);
+        for column in 0..=columns {
+            let x = origin.x + cell * f64::from(column);
+            self.push(Primitive::Line {
+                from: Position::new(x, origin.y),
+                to: Position::new(x, origin.y + height),
+            });
+        }
+        for row in 0..=rows {
+            let y = origin.y + cell * f64::from(row);
+            self.push(Primitive::Line {
+                from: Position::new(origin.x, y),
+                to: Position::new(origin.x + width, y),
+            });
+        }
+        self
+    }
+}
+
+/// A set of [`Layer`]s, drawn in the order they were created.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Canvas {
+    layers: Vec<(String, Layer)>,
+    palette: HashMap<String, String>,
+}
+
+impl Canvas {
+    #[inline]
+    pub fn new() -> Self {
+        Canvas::default()
+    }
+
+    /// The layer called `name`. Creates it, visible and empty, if it doesn't exist.
+    pub fn layer(&mut self, name: &str) -> &mut Layer {
+        let index = match self.layers.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                let layer = Layer {
+                    visible: true,
+                    ..Layer::default()
+                };
+                self.layers.push((name.to_owned(), layer));
+                self.layers.len() - 1
+            }
+        };
+        &mut self.layers[index].1
+    }
+
+    /// The names of the layers, in drawing order.
+    pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Shows or hides a layer.
+    pub fn set_visible(&mut self, name: &str, visible: bool) {
+        self.layer(name).set_visible(visible);
+    }
+
+    /// Shows a hidden layer, or hides a visible one.
+    pub fn toggle(&mut self, name: &str) {
+        let layer = self.layer(name);
+        layer.visible = !layer.visible;
+    }
+
+    /// Removes a layer and its shapes.
+    pub fn remove_layer(&mut self, name: &str) {
+        self.layers.retain(|(n, _)| n != name);
+    }
+
+    /// Removes all shapes from every layer.
+    pub fn clear(&mut self) {
+        for (_, layer) in &mut self.layers {
+            layer.clear();
+        }
+    }
+
+    /// Names `color` (a [`Color`](crate::color::Color) or CSS string), so styles can refer to it as `name`.
+    /// Redefining a color changes it for shapes already drawn with it.
+    pub fn define_color(&mut self, name: &str, color: impl ToCss) {
+        self.palette
+            .insert(name.to_owned(), color.to_css().into_owned());
+    }
+
+    /// The CSS color for a color name, or the color itself if it isn't a defined name.
+    pub fn resolve_color<'a>(&'a self, color: &'a str) -> &'a str {
+        self.palette.get(color).map_or(color, String::as_str)
+    }
+
+    /// Draws the visible layers with `graphics`.
+    #[inline]
+    pub fn flush(&self) {
+        self.flush_to(&mut GraphicsSink::new());
+    }
+
+    /// Draws the visible layers to `sink`.
+    pub fn flush_to(&self, sink: &mut (impl DebugDraw + ?Sized)) {
+        for (_, layer) in self.layers.iter().filter(|(_, layer)| layer.visible) {
+            for (style, primitive) in &layer.shapes {
+                let resolved = self.resolve_color(&style.color);
+                if resolved == style.color {
+                    sink.draw(style, primitive);
+                } else {
+                    let style = Style {
+                        color: resolved.to_owned(),
+                        ..style.clone()
+                    };
+                    sink.draw(&style, primitive);
+                }
+            }
+        }
+    }
+}
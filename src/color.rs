@@ -0,0 +1,468 @@
+//! Colors, for drawing and for reading players' colors.
+//!
+//! Every drawing function takes an [`impl ToCss`](ToCss), so you can pass either a [`Color`] or a CSS string.
+
+use crate::base::bases;
+use crate::{Destructible, EnumerateByID, LivingDestructible};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{borrow::Cow, error::Error, fmt, str::FromStr};
+
+/// An RGBA color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Opacity, from 0 (transparent) to 255 (opaque).
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const RED: Color = Color::rgb(255, 0, 0);
+    pub const GREEN: Color = Color::rgb(0, 128, 0);
+    pub const BLUE: Color = Color::rgb(0, 0, 255);
+    pub const YELLOW: Color = Color::rgb(255, 255, 0);
+    pub const ORANGE: Color = Color::rgb(255, 165, 0);
+    pub const GRAY: Color = Color::rgb(128, 128, 128);
+
+    #[inline]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    #[inline]
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// A color from its hue (in degrees), saturation and lightness (both from 0 to 1).
+    pub fn hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        Color::hsla(hue, saturation, lightness, 1.0)
+    }
+
+    /// A color from its hue (in degrees), saturation, lightness and alpha (all from 0 to 1).
+    pub fn hsla(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = lightness - chroma / 2.0;
+        Color {
+            r: to_channel(r + m),
+            g: to_channel(g + m),
+            b: to_channel(b + m),
+            a: to_channel(alpha),
+        }
+    }
+
+    /// Hue (in degrees), saturation and lightness (both from 0 to 1).
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let (r, g, b) = (
+            f64::from(self.r) / 255.0,
+            f64::from(self.g) / 255.0,
+            f64::from(self.b) / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// Opacity, from 0 to 1.
+    #[inline]
+    pub fn alpha(self) -> f64 {
+        f64::from(self.a) / 255.0
+    }
+
+    /// The same color with opacity `alpha`, from 0 to 1.
+    #[inline]
+    pub fn with_alpha(self, alpha: f64) -> Self {
+        Color {
+            a: to_channel(alpha),
+            ..self
+        }
+    }
+
+    /// Linear interpolation between `self` (at `t = 0`) and `other` (at `t = 1`), alpha included.
+    pub fn mix(self, other: Color, t: f64) -> Self {
+        let lerp =
+            |a: u8, b: u8| to_channel((f64::from(a) + (f64::from(b) - f64::from(a)) * t) / 255.0);
+        Color {
+            r: lerp(self.r, other.r),
+            g: lerp(self.g, other.g),
+            b: lerp(self.b, other.b),
+            a: lerp(self.a, other.a),
+        }
+    }
+
+    /// `self` painted over `background`, using `self`'s alpha.
+    pub fn over(self, background: Color) -> Self {
+        let alpha = self.alpha() + background.alpha() * (1.0 - self.alpha());
+        if alpha == 0.0 {
+            return Color::TRANSPARENT;
+        }
+        let channel = |top: u8, bottom: u8| {
+            to_channel(
+                (f64::from(top) * self.alpha()
+                    + f64::from(bottom) * background.alpha() * (1.0 - self.alpha()))
+                    / 255.0
+                    / alpha,
+            )
+        };
+        Color {
+            r: channel(self.r, background.r),
+            g: channel(self.g, background.g),
+            b: channel(self.b, background.b),
+            a: to_channel(alpha),
+        }
+    }
+
+    /// Moves the lightness towards white by `amount`, from 0 to 1.
+    pub fn lighten(self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Color::hsla(h, s, l + amount, self.alpha())
+    }
+
+    /// Moves the lightness towards black by `amount`, from 0 to 1.
+    #[inline]
+    pub fn darken(self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// The `index`th of a sequence of colors with well-separated hues, for telling many things apart.
+    pub fn distinct(index: u32) -> Self {
+        // Successive multiples of the golden angle are spread evenly around the color wheel.
+        Color::hsl(f64::from(index) * 137.507_764, 0.65, 0.55)
+    }
+
+    /// The color `player_id` plays with, as shown on their base.
+    pub fn of_player(player_id: &str) -> Option<Self> {
+        bases.values().find_map(|base| {
+            let base: &LivingDestructible = base.as_ref();
+            if base.player_id().as_string().as_deref() == Some(player_id) {
+                base.typed_color().ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[inline]
+fn to_channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl Default for Color {
+    #[inline]
+    fn default() -> Self {
+        Color::WHITE
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a == 255 {
+            write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            let alpha = (self.alpha() * 1000.0).round() / 1000.0;
+            write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, alpha)
+        }
+    }
+}
+
+/// The error returned when a string isn't a CSS color this crate understands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color: {:?}", self.0)
+    }
+}
+
+impl Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses hex (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`), `rgb()`, `rgba()`, `hsl()`, `hsla()`,
+    /// and basic named colors.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseColorError(s.to_owned());
+        let css = s.trim().to_ascii_lowercase();
+        if let Some(hex) = css.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(error);
+        }
+        if let Some((function, args)) = css.strip_suffix(')').and_then(|css| css.split_once('(')) {
+            let args: Vec<&str> = args
+                .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            return parse_function(function.trim(), &args).ok_or_else(error);
+        }
+        named(&css).ok_or_else(error)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    // `from_str_radix` would also accept a leading sign.
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => Some(Color::rgb(digit(0)?, digit(1)?, digit(2)?)),
+        4 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(Color::rgb(pair(0)?, pair(2)?, pair(4)?)),
+        8 => Some(Color::rgba(pair(0)?, pair(2)?, pair(4)?, pair(6)?)),
+        _ => None,
+    }
+}
+
+/// A number, or a percentage of `scale`.
+fn parse_number(arg: &str, scale: f64) -> Option<f64> {
+    match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0 * scale),
+        None => arg.parse().ok(),
+    }
+}
+
+fn parse_function(function: &str, args: &[&str]) -> Option<Color> {
+    let alpha = match args.get(3) {
+        Some(alpha) => parse_number(alpha, 1.0)?,
+        None if args.len() == 3 => 1.0,
+        None => return None,
+    };
+    if args.len() > 4 {
+        return None;
+    }
+    match function {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| parse_number(arg, 255.0).map(|c| to_channel(c / 255.0));
+            Some(Color {
+                r: channel(args[0])?,
+                g: channel(args[1])?,
+                b: channel(args[2])?,
+                a: to_channel(alpha),
+            })
+        }
+        "hsl" | "hsla" => Some(Color::hsla(
+            args[0].trim_end_matches("deg").parse().ok()?,
+            parse_number(args[1], 1.0)?,
+            parse_number(args[2], 1.0)?,
+            alpha,
+        )),
+        _ => None,
+    }
+}
+
+fn named(name: &str) -> Option<Color> {
+    Some(match name {
+        "transparent" => Color::TRANSPARENT,
+        "black" => Color::BLACK,
+        "white" => Color::WHITE,
+        "red" => Color::RED,
+        "green" => Color::GREEN,
+        "blue" => Color::BLUE,
+        "yellow" => Color::YELLOW,
+        "orange" => Color::ORANGE,
+        "gray" | "grey" => Color::GRAY,
+        "silver" => Color::rgb(192, 192, 192),
+        "maroon" => Color::rgb(128, 0, 0),
+        "purple" => Color::rgb(128, 0, 128),
+        "fuchsia" | "magenta" => Color::rgb(255, 0, 255),
+        "lime" => Color::rgb(0, 255, 0),
+        "olive" => Color::rgb(128, 128, 0),
+        "navy" => Color::rgb(0, 0, 128),
+        "teal" => Color::rgb(0, 128, 128),
+        "aqua" | "cyan" => Color::rgb(0, 255, 255),
+        "pink" => Color::rgb(255, 192, 203),
+        _ => return None,
+    })
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = Cow::<str>::deserialize(deserializer)?;
+        css.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Anything that can be passed to JS as a CSS color: [`Color`]s and strings.
+pub trait ToCss {
+    fn to_css(&self) -> Cow<'_, str>;
+}
+
+impl ToCss for Color {
+    #[inline]
+    fn to_css(&self) -> Cow<'_, str> {
+        Cow::Owned(self.to_string())
+    }
+}
+
+impl ToCss for str {
+    #[inline]
+    fn to_css(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToCss for String {
+    #[inline]
+    fn to_css(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: ToCss + ?Sized> ToCss for &T {
+    #[inline]
+    fn to_css(&self) -> Cow<'_, str> {
+        (**self).to_css()
+    }
+}
+
+impl Destructible {
+    /// [`color`](Destructible::color), parsed.
+    pub fn typed_color(&self) -> Result<Color, ParseColorError> {
+        self.color().parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(css: &str) -> Option<Color> {
+        css.parse().ok()
+    }
+
+    #[test]
+    fn hex_of_every_length() {
+        assert_eq!(parse("#f80"), Some(Color::rgb(255, 136, 0)));
+        assert_eq!(parse("#f808"), Some(Color::rgba(255, 136, 0, 136)));
+        assert_eq!(parse("#FF8000"), Some(Color::rgb(255, 128, 0)));
+        assert_eq!(parse("#ff800080"), Some(Color::rgba(255, 128, 0, 128)));
+        assert_eq!(parse("  #ABCDEF "), Some(Color::rgb(0xab, 0xcd, 0xef)));
+    }
+
+    #[test]
+    fn hex_rejects_bad_lengths_and_characters() {
+        for css in &[
+            "#",
+            "#f",
+            "#ff",
+            "#fffff",
+            "#fffffff",
+            "#fffffffff",
+            "#ggg",
+            "#ff ff00",
+        ] {
+            assert_eq!(parse(css), None, "{}", css);
+        }
+    }
+
+    #[test]
+    fn hex_rejects_signs() {
+        for css in &["#+f+f+f", "#+fffff", "#-1-1-1", "#+ff", "#+fff+fff"] {
+            assert_eq!(parse(css), None, "{}", css);
+        }
+    }
+
+    #[test]
+    fn hex_rejects_non_ascii() {
+        assert_eq!(parse("#ffé"), None);
+        assert_eq!(parse("#ffffé"), None);
+    }
+
+    #[test]
+    fn hsl_primaries_and_wrapping() {
+        assert_eq!(Color::hsl(0.0, 1.0, 0.5), Color::RED);
+        assert_eq!(Color::hsl(120.0, 1.0, 0.5), Color::rgb(0, 255, 0));
+        assert_eq!(Color::hsl(240.0, 1.0, 0.5), Color::BLUE);
+        assert_eq!(Color::hsl(360.0, 1.0, 0.5), Color::RED);
+        assert_eq!(Color::hsl(-120.0, 1.0, 0.5), Color::BLUE);
+        assert_eq!(Color::hsl(60.0, 1.0, 0.5), Color::YELLOW);
+    }
+
+    #[test]
+    fn hsl_clamps_saturation_and_lightness() {
+        assert_eq!(Color::hsl(200.0, 0.0, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(Color::hsl(200.0, 1.0, 2.0), Color::WHITE);
+        assert_eq!(Color::hsl(200.0, 1.0, -1.0), Color::BLACK);
+        assert_eq!(Color::hsl(0.0, 5.0, 0.5), Color::RED);
+        assert_eq!(Color::hsla(0.0, 1.0, 0.5, 0.5).a, 128);
+    }
+
+    #[test]
+    fn to_hsl_round_trips() {
+        assert_eq!(Color::BLACK.to_hsl(), (0.0, 0.0, 0.0));
+        assert_eq!(Color::WHITE.to_hsl(), (0.0, 0.0, 1.0));
+        for &color in &[
+            Color::RED,
+            Color::GREEN,
+            Color::BLUE,
+            Color::ORANGE,
+            Color::rgb(12, 200, 99),
+            Color::rgb(250, 10, 240),
+        ] {
+            let (h, s, l) = color.to_hsl();
+            assert!((0.0..360.0).contains(&h), "{:?}", color);
+            assert_eq!(Color::hsl(h, s, l), color);
+        }
+    }
+
+    #[test]
+    fn css_functions_and_names() {
+        assert_eq!(parse("rgb(255, 128, 0)"), Some(Color::rgb(255, 128, 0)));
+        assert_eq!(
+            parse("rgba(100%, 0%, 0%, 0.5)"),
+            Some(Color::rgba(255, 0, 0, 128))
+        );
+        assert_eq!(parse("hsl(120deg 100% 50%)"), Some(Color::rgb(0, 255, 0)));
+        assert_eq!(parse("Grey"), Some(Color::GRAY));
+        assert_eq!(parse("rgb(1, 2)"), None);
+        assert_eq!(parse("rgb(1, 2, 3, 4, 5)"), None);
+        assert_eq!(parse("chartreuse"), None);
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for &color in &[Color::ORANGE, Color::rgba(1, 2, 3, 4), Color::TRANSPARENT] {
+            let parsed = parse(&color.to_string()).unwrap();
+            assert_eq!((parsed.r, parsed.g, parsed.b), (color.r, color.g, color.b));
+            assert!((i32::from(parsed.a) - i32::from(color.a)).abs() <= 1);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Commands that can be given to spirits, as plain data.
+//!
+//! Entities are referred to by ID, so commands can be stored, inspected, and replayed
+//! without holding on to JS objects.
+//!
+//! The methods of [`OperableSpirit`](crate::spirit::OperableSpirit) send each command to the game as soon as
+//! they're called. [`Commands`] instead buffers them over the course of a tick, letting later commands override
+//! earlier ones, and sends them all to JS in a single call with [`flush`](Commands::flush).
+
+use crate::game::Game;
+use crate::Position;
+use js_sys::Function;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// A command given to an [`OperableSpirit`](crate::spirit::OperableSpirit).
+///
+/// Serializes as `{"action": "move", "target": [x, y]}`, `{"action": "energize", "target": "star_zxq"}`,
+/// `{"action": "divide"}`, etc.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "target", rename_all = "snake_case")]
+pub enum Command {
+    /// [`move_to_pos`](crate::spirit::OperableSpirit::move_to_pos)
+    Move(Position),
+    /// [`energize`](crate::spirit::OperableSpirit::energize), with the ID of the target entity.
+    Energize(String),
+    /// `merge`, with the ID of the target spirit. Circles only.
+    Merge(String),
+    /// `divide`. Circles only.
+    Divide,
+    /// `jump`. Squares only.
+    Jump(Position),
+    /// `explode`. Triangles only.
+    Explode,
+    /// [`shout`](crate::spirit::OperableSpirit::shout)
+    Shout(String),
+    /// [`set_mark`](crate::spirit::OperableSpirit::set_mark)
+    SetMark(String),
+}
+
+/// Commands in the same slot override each other: a spirit can do at most one thing per slot each tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CommandSlot {
+    /// [`Move`](Command::Move) or [`Jump`](Command::Jump).
+    Movement,
+    /// [`Energize`](Command::Energize), [`Merge`](Command::Merge), [`Divide`](Command::Divide),
+    /// or [`Explode`](Command::Explode).
+    Action,
+    /// [`Shout`](Command::Shout).
+    Shout,
+    /// [`SetMark`](Command::SetMark).
+    Mark,
+}
+
+impl Command {
+    /// The slot this command occupies.
+    #[inline]
+    pub fn slot(&self) -> CommandSlot {
+        match self {
+            Command::Move(_) | Command::Jump(_) => CommandSlot::Movement,
+            Command::Energize(_) | Command::Merge(_) | Command::Divide | Command::Explode => {
+                CommandSlot::Action
+            }
+            Command::Shout(_) => CommandSlot::Shout,
+            Command::SetMark(_) => CommandSlot::Mark,
+        }
+    }
+}
+
+/// A buffer of commands to spirits, sent to the game all at once.
+///
+/// Each spirit has one entry per [`CommandSlot`]; pushing a command replaces the previous one in its slot.
+/// Serializes as a list of `[spirit_id, command]` pairs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Commands {
+    queued: BTreeMap<String, BTreeMap<CommandSlot, Command>>,
+}
+
+impl Commands {
+    #[inline]
+    pub fn new() -> Self {
+        Commands::default()
+    }
+
+    /// Queues a command, replacing any command already queued for this spirit in the same slot.
+    /// Returns the replaced command, if any.
+    pub fn push(&mut self, spirit_id: &str, command: Command) -> Option<Command> {
+        self.queued
+            .entry(spirit_id.to_owned())
+            .or_default()
+            .insert(command.slot(), command)
+    }
+
+    /// Queues a [`Move`](Command::Move).
+    pub fn move_to(&mut self, spirit_id: &str, pos: Position) {
+        self.push(spirit_id, Command::Move(pos));
+    }
+
+    /// Queues an [`Energize`](Command::Energize).
+    pub fn energize(&mut self, spirit_id: &str, target_id: &str) {
+        self.push(spirit_id, Command::Energize(target_id.to_owned()));
+    }
+
+    /// Queues a [`Merge`](Command::Merge).
+    pub fn merge(&mut self, spirit_id: &str, target_id: &str) {
+        self.push(spirit_id, Command::Merge(target_id.to_owned()));
+    }
+
+    /// Queues a [`Divide`](Command::Divide).
+    pub fn divide(&mut self, spirit_id: &str) {
+        self.push(spirit_id, Command::Divide);
+    }
+
+    /// Queues a [`Jump`](Command::Jump).
+    pub fn jump(&mut self, spirit_id: &str, pos: Position) {
+        self.push(spirit_id, Command::Jump(pos));
+    }
+
+    /// Queues an [`Explode`](Command::Explode).
+    pub fn explode(&mut self, spirit_id: &str) {
+        self.push(spirit_id, Command::Explode);
+    }
+
+    /// Queues a [`Shout`](Command::Shout).
+    pub fn shout(&mut self, spirit_id: &str, message: &str) {
+        self.push(spirit_id, Command::Shout(message.to_owned()));
+    }
+
+    /// Queues a [`SetMark`](Command::SetMark).
+    pub fn set_mark(&mut self, spirit_id: &str, label: &str) {
+        self.push(spirit_id, Command::SetMark(label.to_owned()));
+    }
+
+    /// The command queued for this spirit in this slot.
+    pub fn get(&self, spirit_id: &str, slot: CommandSlot) -> Option<&Command> {
+        self.queued.get(spirit_id)?.get(&slot)
+    }
+
+    /// Removes the command queued for this spirit in this slot.
+    pub fn cancel(&mut self, spirit_id: &str, slot: CommandSlot) -> Option<Command> {
+        let queued = self.queued.get_mut(spirit_id)?;
+        let command = queued.remove(&slot);
+        if queued.is_empty() {
+            self.queued.remove(spirit_id);
+        }
+        command
+    }
+
+    /// Removes all commands queued for this spirit.
+    pub fn cancel_all(&mut self, spirit_id: &str) {
+        self.queued.remove(spirit_id);
+    }
+
+    /// Removes all queued commands.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.queued.clear();
+    }
+
+    /// The number of queued commands.
+    pub fn len(&self) -> usize {
+        self.queued.values().map(BTreeMap::len).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// An iterator over the queued commands, ordered by spirit ID and then by slot.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Command)> {
+        self.queued.iter().flat_map(|(spirit_id, queued)| {
+            queued
+                .values()
+                .map(move |command| (spirit_id.as_str(), command))
+        })
+    }
+
+    /// The queued commands, as JSON. Use this to log them, or to replay them later with [`from_json`](Commands::from_json).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Parses commands dumped by [`to_json`](Commands::to_json).
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Gives the queued commands to a [`Game`], without clearing them.
+    pub fn replay(&self, game: &mut impl Game) {
+        for (spirit_id, command) in self.iter() {
+            game.command(spirit_id, command.clone());
+        }
+    }
+
+    /// Sends the queued commands to the game in one call, and clears them.
+    ///
+    /// Fails with the commands the game didn't take: ones to spirits that don't exist, are dead or aren't yours,
+    /// to entities that don't exist, shape-specific commands to spirits of another shape, and ones the game
+    /// threw on. The other commands are still sent.
+    pub fn flush(&mut self) -> Result<(), Commands> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let json = JsValue::from_str(&self.to_json());
+        crate::shim::install();
+        let rejected = FLUSH
+            .with(|flush| flush.call1(&JsValue::NULL, &json))
+            .ok()
+            .and_then(|rejected| rejected.as_string())
+            .and_then(|rejected| Commands::from_json(&rejected).ok());
+        let queued = std::mem::take(self);
+        match rejected {
+            Some(rejected) if rejected.is_empty() => Ok(()),
+            Some(rejected) => Err(rejected),
+            None => Err(queued),
+        }
+    }
+}
+
+impl Serialize for Commands {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Commands {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut commands = Commands::new();
+        for (spirit_id, command) in Vec::<(String, Command)>::deserialize(deserializer)? {
+            commands.push(&spirit_id, command);
+        }
+        Ok(commands)
+    }
+}
+
+impl Extend<(String, Command)> for Commands {
+    fn extend<I: IntoIterator<Item = (String, Command)>>(&mut self, iter: I) {
+        for (spirit_id, command) in iter {
+            self.push(&spirit_id, command);
+        }
+    }
+}
+
+thread_local! {
+    /// Applies a JSON list of `[spirit_id, command]` pairs, as serialized by [`Commands`], and returns the pairs
+    /// it couldn't apply, in the same format.
+    static FLUSH: Function = Function::new_with_args(
+        "commands",
+        r#"
+        const rejected = [];
+        for (const pair of JSON.parse(commands)) {
+            const [id, command] = pair;
+            const spirit = spirits[id];
+            const method = spirit && spirit[command.action];
+            if (!spirit || spirit.player_id !== this_player_id || !(spirit.hp > 0) || typeof method !== "function") {
+                rejected.push(pair);
+                continue;
+            }
+            const target = command.target;
+            let args;
+            switch (command.action) {
+                case "energize": args = [__yareio_sys.entity(target)]; break;
+                case "merge": args = [spirits[target]]; break;
+                case "divide": case "explode": args = []; break;
+                default: args = [target];
+            }
+            if (args.includes(undefined)) {
+                rejected.push(pair);
+                continue;
+            }
+            try {
+                method.apply(spirit, args);
+            } catch (e) {
+                rejected.push(pair);
+            }
+        }
+        return JSON.stringify(rejected);
+        "#,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_serialize_as_id_command_pairs() {
+        let mut commands = Commands::new();
+        commands.move_to("p1_1", Position::new(1.0, 2.0));
+        commands.energize("p1_1", "star_zxq");
+        commands.divide("p1_2");
+        commands.shout("p1_2", "hi");
+        assert_eq!(
+            commands.to_json(),
+            r#"[["p1_1",{"action":"move","target":[1.0,2.0]}],["p1_1",{"action":"energize","target":"star_zxq"}],["p1_2",{"action":"divide"}],["p1_2",{"action":"shout","target":"hi"}]]"#
+        );
+        assert_eq!(Commands::from_json(&commands.to_json()).unwrap(), commands);
+    }
+
+    #[test]
+    fn later_commands_in_a_slot_win_when_deserializing() {
+        let commands = Commands::from_json(
+            r#"[["p1_1",{"action":"move","target":[0,0]}],["p1_1",{"action":"jump","target":[5,5]}],["p1_1",{"action":"explode"}]]"#,
+        )
+        .unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands.get("p1_1", CommandSlot::Movement),
+            Some(&Command::Jump(Position::new(5.0, 5.0)))
+        );
+        assert_eq!(
+            commands.get("p1_1", CommandSlot::Action),
+            Some(&Command::Explode)
+        );
+    }
+
+    #[test]
+    fn unknown_actions_fail_to_parse() {
+        assert!(Commands::from_json(r#"[["p1_1",{"action":"teleport","target":[0,0]}]]"#).is_err());
+        assert!(Commands::from_json(r#"[["p1_1",{"action":"move"}]]"#).is_err());
+    }
+}
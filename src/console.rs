@@ -1,5 +1,7 @@
-//! Provides access to `console.log()`.
+//! Provides access to `console.log()`, directly or as a backend for the [`log`](::log) crate.
 
+use ::log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -16,3 +18,95 @@ macro_rules! log {
         $crate::console::log(::std::boxed::Box::from([$(JsValue::from($arg),)+]));
     }
 }
+
+/// A [`log`](::log) backend that writes records to `console.log`, as `[LEVEL tick module] message`.
+///
+/// When the same message is logged several times in a row, only the first one is printed,
+/// followed by a count once a different message comes along (or on [`flush`](::log::Log::flush)).
+/// Install it with [`init_logger`].
+#[derive(Debug)]
+pub struct ConsoleLogger {
+    last: Mutex<Option<LastMessage>>,
+}
+
+/// The last message printed, without its tick, and how many times it has been repeated since.
+#[derive(Debug)]
+struct LastMessage {
+    level: Level,
+    module: String,
+    text: String,
+    repeats: u32,
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger {
+    last: Mutex::new(None),
+};
+
+/// Routes the [`log`](::log) crate's macros to `console.log`, showing records up to `level`.
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    ::log::set_logger(&LOGGER)?;
+    ::log::set_max_level(level);
+    Ok(())
+}
+
+/// Changes the most verbose level that gets logged. Can be called at any time.
+#[inline]
+pub fn set_max_level(level: LevelFilter) {
+    ::log::set_max_level(level);
+}
+
+#[inline]
+fn print(line: String) {
+    log(Box::from([JsValue::from(line)]));
+}
+
+fn print_repeats(repeats: u32) {
+    match repeats {
+        0 => {}
+        1 => print("(previous message repeated once)".to_owned()),
+        _ => print(format!("(previous message repeated {} times)", repeats)),
+    }
+}
+
+impl Log for ConsoleLogger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= ::log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let module = record.module_path().unwrap_or_else(|| record.target());
+        let text = record.args().to_string();
+        let mut last = self.last.lock().unwrap();
+        if let Some(last) = &mut *last {
+            if last.level == record.level() && last.module == module && last.text == text {
+                last.repeats += 1;
+                return;
+            }
+            print_repeats(last.repeats);
+        }
+        print(format!(
+            "[{} {} {}] {}",
+            record.level(),
+            crate::current_tick(),
+            module,
+            text
+        ));
+        *last = Some(LastMessage {
+            level: record.level(),
+            module: module.to_owned(),
+            text,
+            repeats: 0,
+        });
+    }
+
+    fn flush(&self) {
+        if let Some(last) = &mut *self.last.lock().unwrap() {
+            print_repeats(last.repeats);
+            last.repeats = 0;
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! A common interface over the places debug drawings can go.
+//!
+//! Write drawing code against [`DebugDraw`], and pick the sink at build or run time: [`GraphicsSink`] for the
+//! built-in graphics, [`RenderServiceSink`] for `yare-code-sync`'s `RenderService`, [`NullSink`] to turn drawing
+//! off, or [`RecordingSink`] to inspect or replay what was drawn.
+
+use crate::canvas::{Primitive, Style};
+use crate::color::ToCss;
+use crate::{console, graphics, Position};
+use wasm_bindgen::JsValue;
+
+/// Something debug drawings can be sent to.
+///
+/// Colors are [`Color`](crate::color::Color)s or CSS strings, passed by reference so sinks can be used as trait
+/// objects. Sinks that can't draw text, pings or log messages ignore them.
+pub trait DebugDraw {
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, width: f64);
+
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, width: f64);
+
+    fn rect(&mut self, top_left: Position, bottom_right: Position, color: &dyn ToCss, width: f64) {
+        let top_right = Position::new(bottom_right.x, top_left.y);
+        let bottom_left = Position::new(top_left.x, bottom_right.y);
+        self.line(top_left, top_right, color, width);
+        self.line(top_right, bottom_right, color, width);
+        self.line(bottom_right, bottom_left, color, width);
+        self.line(bottom_left, top_left, color, width);
+    }
+
+    fn text(&mut self, _pos: Position, _text: &str, _color: &dyn ToCss) {}
+
+    /// Briefly highlights a position.
+    fn ping(&mut self, _pos: Position) {}
+
+    fn log(&mut self, _message: &str) {}
+
+    /// Draws a [`Canvas`](crate::canvas::Canvas) primitive. `style.color` must already be a CSS color.
+    fn draw(&mut self, style: &Style, primitive: &Primitive) {
+        match primitive {
+            Primitive::Line { from, to } => self.line(*from, *to, &style.color, style.width),
+            Primitive::Circle { center, radius } => {
+                self.circle(*center, *radius, &style.color, style.width)
+            }
+            Primitive::Rect {
+                top_left,
+                bottom_right,
+            } => self.rect(*top_left, *bottom_right, &style.color, style.width),
+            Primitive::Text { pos, text } => self.text(*pos, text, &style.color),
+        }
+    }
+}
+
+impl<T: DebugDraw + ?Sized> DebugDraw for &mut T {
+    #[inline]
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, width: f64) {
+        (**self).line(from, to, color, width)
+    }
+
+    #[inline]
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, width: f64) {
+        (**self).circle(center, radius, color, width)
+    }
+
+    #[inline]
+    fn rect(&mut self, top_left: Position, bottom_right: Position, color: &dyn ToCss, width: f64) {
+        (**self).rect(top_left, bottom_right, color, width)
+    }
+
+    #[inline]
+    fn text(&mut self, pos: Position, text: &str, color: &dyn ToCss) {
+        (**self).text(pos, text, color)
+    }
+
+    #[inline]
+    fn ping(&mut self, pos: Position) {
+        (**self).ping(pos)
+    }
+
+    #[inline]
+    fn log(&mut self, message: &str) {
+        (**self).log(message)
+    }
+}
+
+impl<T: DebugDraw + ?Sized> DebugDraw for Box<T> {
+    #[inline]
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, width: f64) {
+        (**self).line(from, to, color, width)
+    }
+
+    #[inline]
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, width: f64) {
+        (**self).circle(center, radius, color, width)
+    }
+
+    #[inline]
+    fn rect(&mut self, top_left: Position, bottom_right: Position, color: &dyn ToCss, width: f64) {
+        (**self).rect(top_left, bottom_right, color, width)
+    }
+
+    #[inline]
+    fn text(&mut self, pos: Position, text: &str, color: &dyn ToCss) {
+        (**self).text(pos, text, color)
+    }
+
+    #[inline]
+    fn ping(&mut self, pos: Position) {
+        (**self).ping(pos)
+    }
+
+    #[inline]
+    fn log(&mut self, message: &str) {
+        (**self).log(message)
+    }
+}
+
+/// Draws with the built-in [`graphics`](crate::graphics), only setting the style and line width when they change.
+/// Logs go to `console.log`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphicsSink {
+    color: Option<String>,
+    width: Option<f64>,
+}
+
+impl GraphicsSink {
+    #[inline]
+    pub fn new() -> Self {
+        GraphicsSink::default()
+    }
+
+    fn set(&mut self, color: &dyn ToCss, width: f64) {
+        let color = color.to_css();
+        if self.color.as_deref() != Some(&*color) {
+            graphics::set_style(&*color);
+            self.color = Some(color.into_owned());
+        }
+        if self.width != Some(width) {
+            graphics::set_linewidth(width);
+            self.width = Some(width);
+        }
+    }
+}
+
+impl DebugDraw for GraphicsSink {
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, width: f64) {
+        self.set(color, width);
+        graphics::line(from, to);
+    }
+
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, width: f64) {
+        self.set(color, width);
+        graphics::circle(center, radius);
+    }
+
+    fn rect(&mut self, top_left: Position, bottom_right: Position, color: &dyn ToCss, width: f64) {
+        self.set(color, width);
+        graphics::rect(top_left, bottom_right);
+    }
+
+    fn log(&mut self, message: &str) {
+        console::log(Box::from([JsValue::from(message)]));
+    }
+}
+
+/// Draws with `RenderService`. It has no line widths, so those are ignored.
+///
+/// Requires the `"RenderService"` crate feature.
+#[cfg(feature = "RenderService")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderServiceSink;
+
+#[cfg(feature = "RenderService")]
+impl DebugDraw for RenderServiceSink {
+    #[inline]
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, _width: f64) {
+        crate::render_service::line_color(from, to, color);
+    }
+
+    #[inline]
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, _width: f64) {
+        crate::render_service::circle_color(center, radius, color);
+    }
+
+    #[inline]
+    fn text(&mut self, pos: Position, text: &str, color: &dyn ToCss) {
+        crate::render_service::text_color(pos, text, color);
+    }
+
+    #[inline]
+    fn ping(&mut self, pos: Position) {
+        crate::render_service::ping(pos);
+    }
+
+    #[inline]
+    fn log(&mut self, message: &str) {
+        crate::render_service::log(message);
+    }
+}
+
+/// Discards everything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NullSink;
+
+impl DebugDraw for NullSink {
+    #[inline]
+    fn line(&mut self, _from: Position, _to: Position, _color: &dyn ToCss, _width: f64) {}
+
+    #[inline]
+    fn circle(&mut self, _center: Position, _radius: f64, _color: &dyn ToCss, _width: f64) {}
+}
+
+/// A call made to a [`RecordingSink`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    Line {
+        from: Position,
+        to: Position,
+        color: String,
+        width: f64,
+    },
+    Circle {
+        center: Position,
+        radius: f64,
+        color: String,
+        width: f64,
+    },
+    Rect {
+        top_left: Position,
+        bottom_right: Position,
+        color: String,
+        width: f64,
+    },
+    Text {
+        pos: Position,
+        text: String,
+        color: String,
+    },
+    Ping(Position),
+    Log(String),
+}
+
+/// Records calls instead of drawing them, for tests and for replaying them into another sink.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordingSink {
+    pub calls: Vec<DrawCall>,
+}
+
+impl RecordingSink {
+    #[inline]
+    pub fn new() -> Self {
+        RecordingSink::default()
+    }
+
+    /// Makes the recorded calls again on `sink`.
+    pub fn replay(&self, sink: &mut (impl DebugDraw + ?Sized)) {
+        for call in &self.calls {
+            match call {
+                DrawCall::Line {
+                    from,
+                    to,
+                    color,
+                    width,
+                } => sink.line(*from, *to, color, *width),
+                DrawCall::Circle {
+                    center,
+                    radius,
+                    color,
+                    width,
+                } => sink.circle(*center, *radius, color, *width),
+                DrawCall::Rect {
+                    top_left,
+                    bottom_right,
+                    color,
+                    width,
+                } => sink.rect(*top_left, *bottom_right, color, *width),
+                DrawCall::Text { pos, text, color } => sink.text(*pos, text, color),
+                DrawCall::Ping(pos) => sink.ping(*pos),
+                DrawCall::Log(message) => sink.log(message),
+            }
+        }
+    }
+}
+
+impl DebugDraw for RecordingSink {
+    fn line(&mut self, from: Position, to: Position, color: &dyn ToCss, width: f64) {
+        self.calls.push(DrawCall::Line {
+            from,
+            to,
+            color: color.to_css().into_owned(),
+            width,
+        });
+    }
+
+    fn circle(&mut self, center: Position, radius: f64, color: &dyn ToCss, width: f64) {
+        self.calls.push(DrawCall::Circle {
+            center,
+            radius,
+            color: color.to_css().into_owned(),
+            width,
+        });
+    }
+
+    fn rect(&mut self, top_left: Position, bottom_right: Position, color: &dyn ToCss, width: f64) {
+        self.calls.push(DrawCall::Rect {
+            top_left,
+            bottom_right,
+            color: color.to_css().into_owned(),
+            width,
+        });
+    }
+
+    fn text(&mut self, pos: Position, text: &str, color: &dyn ToCss) {
+        self.calls.push(DrawCall::Text {
+            pos,
+            text: text.to_owned(),
+            color: color.to_css().into_owned(),
+        });
+    }
+
+    fn ping(&mut self, pos: Position) {
+        self.calls.push(DrawCall::Ping(pos));
+    }
+
+    fn log(&mut self, message: &str) {
+        self.calls.push(DrawCall::Log(message.to_owned()));
+    }
+}
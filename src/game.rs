@@ -0,0 +1,422 @@
+//! A common interface over the live game, snapshots, and test fixtures.
+//!
+//! Bot logic written against the [`GameView`] and [`Game`] traits, rather than the statics in the rest of this crate,
+//! runs unchanged against [`Live`] in Yare, against a [`Snapshot`], against a hand-built [`Fixture`],
+//! or (with the `sim` feature) against a [`Simulation`](crate::sim::Simulation).
+//!
+//! Entities are returned as the owned structs of the [`snapshot`](crate::snapshot) module.
+//! Implementations that already hold them return borrows; [`Live`] reads them from JS on every call.
+
+use crate::base::{bases, Base};
+use crate::commands::Command;
+use crate::outpost::{outposts, Outpost};
+use crate::players::this_player_id;
+use crate::snapshot::{BaseSnapshot, OutpostSnapshot, Snapshot, SpiritSnapshot, StarSnapshot};
+use crate::spirit::{spirits, FriendlySpirit, OperableSpirit, Spirit, TypedSpirit};
+use crate::star::{stars, Star};
+use crate::{current_tick, Entity, EnumerateByID, LivingDestructible, Position};
+use js_sys::{Object, Reflect};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// Iterator over entities returned by [`GameView`]'s methods.
+pub type Entities<'a, T> = Box<dyn Iterator<Item = Cow<'a, T>> + 'a>;
+
+/// Read access to the game state.
+pub trait GameView {
+    /// The current tick.
+    fn tick(&self) -> u32;
+
+    /// Your player ID.
+    fn this_player_id(&self) -> Cow<'_, str>;
+
+    /// All spirits, dead or alive.
+    fn spirits(&self) -> Entities<'_, SpiritSnapshot>;
+
+    /// The spirit with this ID.
+    fn spirit_by_id(&self, id: &str) -> Option<Cow<'_, SpiritSnapshot>>;
+
+    /// All bases.
+    fn bases(&self) -> Entities<'_, BaseSnapshot>;
+
+    /// The base with this ID.
+    fn base_by_id(&self, id: &str) -> Option<Cow<'_, BaseSnapshot>>;
+
+    /// All outposts.
+    fn outposts(&self) -> Entities<'_, OutpostSnapshot>;
+
+    /// The outpost with this ID.
+    fn outpost_by_id(&self, id: &str) -> Option<Cow<'_, OutpostSnapshot>>;
+
+    /// All stars.
+    fn stars(&self) -> Entities<'_, StarSnapshot>;
+
+    /// The star with this ID.
+    fn star_by_id(&self, id: &str) -> Option<Cow<'_, StarSnapshot>>;
+
+    /// Your living spirits.
+    fn operable_spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(self.spirits().filter(|spirit| spirit.is_operable()))
+    }
+
+    /// Living spirits that aren't yours.
+    fn living_enemy_spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(
+            self.spirits()
+                .filter(|spirit| !spirit.friendly && spirit.is_living()),
+        )
+    }
+
+    /// Your base.
+    fn base(&self) -> Option<Cow<'_, BaseSnapshot>> {
+        self.bases().find(|base| base.friendly)
+    }
+
+    /// The enemy base.
+    fn enemy_base(&self) -> Option<Cow<'_, BaseSnapshot>> {
+        self.bases().find(|base| !base.friendly)
+    }
+}
+
+/// Read access to the game state, plus the ability to command your spirits.
+pub trait Game: GameView {
+    /// Gives a command to one of your spirits.
+    fn command(&mut self, spirit_id: &str, command: Command);
+
+    /// Shorthand for [`Command::Move`].
+    fn move_to(&mut self, spirit_id: &str, pos: Position) {
+        self.command(spirit_id, Command::Move(pos));
+    }
+
+    /// Shorthand for [`Command::Energize`].
+    fn energize(&mut self, spirit_id: &str, target_id: &str) {
+        self.command(spirit_id, Command::Energize(target_id.to_owned()));
+    }
+
+    /// Shorthand for [`Command::Merge`].
+    fn merge(&mut self, spirit_id: &str, target_id: &str) {
+        self.command(spirit_id, Command::Merge(target_id.to_owned()));
+    }
+
+    /// Shorthand for [`Command::Divide`].
+    fn divide(&mut self, spirit_id: &str) {
+        self.command(spirit_id, Command::Divide);
+    }
+
+    /// Shorthand for [`Command::Jump`].
+    fn jump(&mut self, spirit_id: &str, pos: Position) {
+        self.command(spirit_id, Command::Jump(pos));
+    }
+
+    /// Shorthand for [`Command::Explode`].
+    fn explode(&mut self, spirit_id: &str) {
+        self.command(spirit_id, Command::Explode);
+    }
+
+    /// Shorthand for [`Command::Shout`].
+    fn shout(&mut self, spirit_id: &str, message: &str) {
+        self.command(spirit_id, Command::Shout(message.to_owned()));
+    }
+
+    /// Shorthand for [`Command::SetMark`].
+    fn set_mark(&mut self, spirit_id: &str, label: &str) {
+        self.command(spirit_id, Command::SetMark(label.to_owned()));
+    }
+}
+
+// Live
+
+/// The live game, through the statics in the rest of this crate.
+///
+/// Each query reads entities property by property; prefer capturing a [`Snapshot`] when reading a lot of state.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Live;
+
+/// Looks up `id` in one of the global ID-to-entity mappings.
+pub(crate) fn lookup<T: JsCast>(table: &Object, id: &str) -> Option<T> {
+    let value = Reflect::get(table, &JsValue::from_str(id)).ok()?;
+    if value.is_undefined() {
+        None
+    } else {
+        Some(value.unchecked_into())
+    }
+}
+
+impl GameView for Live {
+    #[inline]
+    fn tick(&self) -> u32 {
+        current_tick()
+    }
+
+    fn this_player_id(&self) -> Cow<'_, str> {
+        Cow::Owned(this_player_id.as_string().unwrap_or_default())
+    }
+
+    fn spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(
+            spirits
+                .values()
+                .map(|spirit| Cow::Owned(SpiritSnapshot::from(&spirit))),
+        )
+    }
+
+    fn spirit_by_id(&self, id: &str) -> Option<Cow<'_, SpiritSnapshot>> {
+        lookup::<Spirit>(&spirits, id).map(|spirit| Cow::Owned(SpiritSnapshot::from(&spirit)))
+    }
+
+    fn bases(&self) -> Entities<'_, BaseSnapshot> {
+        Box::new(
+            bases
+                .values()
+                .map(|base| Cow::Owned(BaseSnapshot::from(&base))),
+        )
+    }
+
+    fn base_by_id(&self, id: &str) -> Option<Cow<'_, BaseSnapshot>> {
+        lookup::<Base>(&bases, id).map(|base| Cow::Owned(BaseSnapshot::from(&base)))
+    }
+
+    fn outposts(&self) -> Entities<'_, OutpostSnapshot> {
+        Box::new(
+            outposts
+                .values()
+                .map(|outpost| Cow::Owned(OutpostSnapshot::from(&outpost))),
+        )
+    }
+
+    fn outpost_by_id(&self, id: &str) -> Option<Cow<'_, OutpostSnapshot>> {
+        lookup::<Outpost>(&outposts, id).map(|outpost| Cow::Owned(OutpostSnapshot::from(&outpost)))
+    }
+
+    fn stars(&self) -> Entities<'_, StarSnapshot> {
+        Box::new(
+            stars
+                .values()
+                .map(|star| Cow::Owned(StarSnapshot::from(&star))),
+        )
+    }
+
+    fn star_by_id(&self, id: &str) -> Option<Cow<'_, StarSnapshot>> {
+        lookup::<Star>(&stars, id).map(|star| Cow::Owned(StarSnapshot::from(&star)))
+    }
+
+    /// Filters in JS, so only your living spirits are read.
+    fn operable_spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(spirits.operable().map(|spirit| {
+            let spirit: &FriendlySpirit = spirit.as_ref();
+            Cow::Owned(SpiritSnapshot::from(spirit.as_ref()))
+        }))
+    }
+
+    /// Filters in JS, so only living enemy spirits are read.
+    fn living_enemy_spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(
+            spirits
+                .living_enemies()
+                .map(|spirit| Cow::Owned(SpiritSnapshot::from(spirit.as_ref()))),
+        )
+    }
+
+    /// Only reads the base that matches.
+    fn base(&self) -> Option<Cow<'_, BaseSnapshot>> {
+        find_base(true)
+    }
+
+    /// Only reads the base that matches.
+    fn enemy_base(&self) -> Option<Cow<'_, BaseSnapshot>> {
+        find_base(false)
+    }
+}
+
+/// Your base, or the enemy's, compared by player ID before anything else is read.
+fn find_base(friendly: bool) -> Option<Cow<'static, BaseSnapshot>> {
+    let me: &JsValue = this_player_id.as_ref();
+    bases
+        .values()
+        .find(|base| {
+            let base: &LivingDestructible = base.as_ref();
+            let player_id: JsValue = base.player_id().into();
+            (&player_id == me) == friendly
+        })
+        .map(|base| Cow::Owned(BaseSnapshot::from(&base)))
+}
+
+/// Looks up any entity by ID.
+pub(crate) fn lookup_entity(id: &str) -> Option<Entity> {
+    crate::shim::entity(id)
+}
+
+impl Game for Live {
+    /// Sends the command to the game right away.
+    ///
+    /// Commands to spirits that don't exist, are dead or aren't yours, or to entities that don't exist, are
+    /// ignored. Shape-specific commands to spirits of another shape are ignored.
+    fn command(&mut self, spirit_id: &str, command: Command) {
+        let spirit = match lookup::<Spirit>(&spirits, spirit_id)
+            .and_then(|spirit| OperableSpirit::try_from(spirit).ok())
+        {
+            Some(spirit) => spirit,
+            None => return,
+        };
+        match command {
+            Command::Move(pos) => spirit.move_to_pos(pos),
+            Command::Energize(target_id) => {
+                if let Some(target) = lookup_entity(&target_id) {
+                    spirit.energize(&target);
+                }
+            }
+            Command::Merge(target_id) => {
+                if let (TypedSpirit::Circle(circle), Some(target)) = (
+                    TypedSpirit::from(spirit),
+                    lookup::<Spirit>(&spirits, &target_id),
+                ) {
+                    circle.merge(&target);
+                }
+            }
+            Command::Divide => {
+                if let TypedSpirit::Circle(circle) = TypedSpirit::from(spirit) {
+                    circle.divide();
+                }
+            }
+            Command::Jump(pos) => {
+                if let TypedSpirit::Square(square) = TypedSpirit::from(spirit) {
+                    square.jump(pos);
+                }
+            }
+            Command::Explode => {
+                if let TypedSpirit::Triangle(triangle) = TypedSpirit::from(spirit) {
+                    triangle.explode();
+                }
+            }
+            Command::Shout(message) => spirit.shout(&message),
+            Command::SetMark(label) => spirit.set_mark(&label),
+        }
+    }
+}
+
+// Snapshot
+
+impl GameView for Snapshot {
+    #[inline]
+    fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    #[inline]
+    fn this_player_id(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.this_player_id)
+    }
+
+    fn spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        Box::new(self.spirits.values().map(Cow::Borrowed))
+    }
+
+    fn spirit_by_id(&self, id: &str) -> Option<Cow<'_, SpiritSnapshot>> {
+        self.spirits.get(id).map(Cow::Borrowed)
+    }
+
+    fn bases(&self) -> Entities<'_, BaseSnapshot> {
+        Box::new(self.bases.values().map(Cow::Borrowed))
+    }
+
+    fn base_by_id(&self, id: &str) -> Option<Cow<'_, BaseSnapshot>> {
+        self.bases.get(id).map(Cow::Borrowed)
+    }
+
+    fn outposts(&self) -> Entities<'_, OutpostSnapshot> {
+        Box::new(self.outposts.values().map(Cow::Borrowed))
+    }
+
+    fn outpost_by_id(&self, id: &str) -> Option<Cow<'_, OutpostSnapshot>> {
+        self.outposts.get(id).map(Cow::Borrowed)
+    }
+
+    fn stars(&self) -> Entities<'_, StarSnapshot> {
+        Box::new(self.stars.values().map(Cow::Borrowed))
+    }
+
+    fn star_by_id(&self, id: &str) -> Option<Cow<'_, StarSnapshot>> {
+        self.stars.get(id).map(Cow::Borrowed)
+    }
+}
+
+// Fixture
+
+/// A hand-built game state that records the commands it's given, for tests.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fixture {
+    /// The state returned by the [`GameView`] methods.
+    pub state: Snapshot,
+    /// Every command given so far, in order.
+    pub commands: Vec<(String, Command)>,
+}
+
+impl Fixture {
+    #[inline]
+    pub fn new(state: Snapshot) -> Self {
+        Fixture {
+            state,
+            commands: Vec::new(),
+        }
+    }
+
+    /// The commands given to one spirit, in order.
+    pub fn commands_for<'a>(&'a self, spirit_id: &'a str) -> impl Iterator<Item = &'a Command> {
+        self.commands
+            .iter()
+            .filter(move |(id, _)| id == spirit_id)
+            .map(|(_, command)| command)
+    }
+}
+
+impl GameView for Fixture {
+    #[inline]
+    fn tick(&self) -> u32 {
+        self.state.tick()
+    }
+
+    #[inline]
+    fn this_player_id(&self) -> Cow<'_, str> {
+        self.state.this_player_id()
+    }
+
+    fn spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        self.state.spirits()
+    }
+
+    fn spirit_by_id(&self, id: &str) -> Option<Cow<'_, SpiritSnapshot>> {
+        self.state.spirit_by_id(id)
+    }
+
+    fn bases(&self) -> Entities<'_, BaseSnapshot> {
+        self.state.bases()
+    }
+
+    fn base_by_id(&self, id: &str) -> Option<Cow<'_, BaseSnapshot>> {
+        self.state.base_by_id(id)
+    }
+
+    fn outposts(&self) -> Entities<'_, OutpostSnapshot> {
+        self.state.outposts()
+    }
+
+    fn outpost_by_id(&self, id: &str) -> Option<Cow<'_, OutpostSnapshot>> {
+        self.state.outpost_by_id(id)
+    }
+
+    fn stars(&self) -> Entities<'_, StarSnapshot> {
+        self.state.stars()
+    }
+
+    fn star_by_id(&self, id: &str) -> Option<Cow<'_, StarSnapshot>> {
+        self.state.star_by_id(id)
+    }
+}
+
+impl Game for Fixture {
+    #[inline]
+    fn command(&mut self, spirit_id: &str, command: Command) {
+        self.commands.push((spirit_id.to_owned(), command));
+    }
+}
@@ -1,5 +1,6 @@
 //! Provides access to the built-in graphics methods.
 
+use crate::color::ToCss;
 use crate::prelude::*;
 use js_sys::Object;
 use wasm_bindgen::prelude::*;
@@ -22,15 +23,6 @@ extern "C" {
     #[wasm_bindgen(method, setter)]
     fn set_linewidth(this: &Graphics, linewidth: f64);
 
-    #[wasm_bindgen(method)]
-    fn line(this: &Graphics, pos: Position, end: Position);
-
-    #[wasm_bindgen(method)]
-    fn circle(this: &Graphics, pos: Position, r: f64);
-
-    #[wasm_bindgen(method)]
-    fn rect(this: &Graphics, tl: Position, br: Position);
-
     #[wasm_bindgen]
     static graphics: Graphics;
 }
@@ -40,9 +32,10 @@ pub fn style() -> String {
     graphics.style()
 }
 
+/// Accepts a [`Color`](crate::color::Color) or a CSS string.
 #[inline(always)]
-pub fn set_style(style: &str) {
-    graphics.set_style(style);
+pub fn set_style(style: impl ToCss) {
+    graphics.set_style(&style.to_css());
 }
 
 #[inline(always)]
@@ -57,15 +50,15 @@ pub fn set_linewidth(linewidth: f64) {
 
 #[inline(always)]
 pub fn line(pos: Position, end: Position) {
-    graphics.line(pos, end);
+    crate::shim::line(pos, end);
 }
 
 #[inline(always)]
 pub fn circle(pos: Position, r: f64) {
-    graphics.circle(pos, r);
+    crate::shim::circle(pos, r);
 }
 
 #[inline(always)]
 pub fn rect(tl: Position, br: Position) {
-    graphics.rect(tl, br);
+    crate::shim::rect(tl, br);
 }
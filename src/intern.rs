@@ -0,0 +1,255 @@
+//! Compact, hashable handles for entity IDs.
+//!
+//! The ID types are backed by JS strings: comparing them calls into JS, and they can't be hashed or ordered.
+//! [`Idx::of`] interns an ID once, reading its string from JS, and returns a `Copy` handle that can. Handles are
+//! stable for the whole game, so they can key state kept across ticks:
+//!
+//! ```ignore
+//! let mut roles: HashMap<SpiritIdx, Role> = HashMap::new();
+//! for spirit in spirits.operable() {
+//!     let role = roles.entry(Idx::of(&spirit.id())).or_insert(Role::Harvester);
+//! }
+//! ```
+//!
+//! Handles of different ID types share one registry, so [`cast`](Idx::cast) between them is free.
+
+use crate::base::BaseID;
+use crate::outpost::OutpostID;
+use crate::spirit::{LivingEnemySpiritID, OperableSpiritID, SpiritID};
+use crate::star::StarID;
+use crate::{EntityID, StructureID};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cell::RefCell, cmp::Ordering, collections::HashMap, fmt, hash::Hash, hash::Hasher,
+    marker::PhantomData,
+};
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Default)]
+struct Registry {
+    names: Vec<String>,
+    indices: HashMap<String, u32>,
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+fn intern(name: &str) -> u32 {
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(&index) = registry.indices.get(name) {
+            return index;
+        }
+        let index = registry.names.len() as u32;
+        registry.names.push(name.to_owned());
+        registry.indices.insert(name.to_owned(), index);
+        index
+    })
+}
+
+/// The number of distinct IDs interned so far.
+pub fn interned_count() -> usize {
+    REGISTRY.with(|registry| registry.borrow().names.len())
+}
+
+/// An interned ID of type `ID`.
+pub struct Idx<ID> {
+    index: u32,
+    marker: PhantomData<fn() -> ID>,
+}
+
+pub type EntityIdx = Idx<EntityID>;
+pub type StructureIdx = Idx<StructureID>;
+pub type SpiritIdx = Idx<SpiritID>;
+pub type OperableSpiritIdx = Idx<OperableSpiritID>;
+pub type LivingEnemySpiritIdx = Idx<LivingEnemySpiritID>;
+pub type BaseIdx = Idx<BaseID>;
+pub type OutpostIdx = Idx<OutpostID>;
+pub type StarIdx = Idx<StarID>;
+
+impl<ID> Idx<ID> {
+    /// The handle for an ID string, interning it if it's new.
+    #[inline]
+    pub fn from_name(name: &str) -> Self {
+        Idx {
+            index: intern(name),
+            marker: PhantomData,
+        }
+    }
+
+    /// The handle's position in the registry, in order of interning.
+    #[inline]
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    /// The ID string.
+    pub fn name(self) -> String {
+        REGISTRY.with(|registry| registry.borrow().names[self.index as usize].clone())
+    }
+
+    /// The same handle, as another ID type. No checks are made.
+    #[inline]
+    pub fn cast<U>(self) -> Idx<U> {
+        Idx {
+            index: self.index,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<ID: JsCast> Idx<ID> {
+    /// The handle for `id`, interning it if it's new.
+    #[inline]
+    pub fn of(id: &ID) -> Self {
+        Idx::from_name(&id.as_ref().as_string().unwrap_or_default())
+    }
+
+    /// The typed ID, as a new JS string.
+    #[inline]
+    pub fn to_id(self) -> ID {
+        JsValue::from(self.name()).unchecked_into()
+    }
+}
+
+impl<ID: JsCast> From<&ID> for Idx<ID> {
+    #[inline]
+    fn from(id: &ID) -> Self {
+        Idx::of(id)
+    }
+}
+
+impl<ID> Clone for Idx<ID> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<ID> Copy for Idx<ID> {}
+
+impl<ID> PartialEq for Idx<ID> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<ID> Eq for Idx<ID> {}
+
+impl<ID> PartialOrd for Idx<ID> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders by interning order, not by ID string.
+impl<ID> Ord for Idx<ID> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<ID> Hash for Idx<ID> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<ID> fmt::Debug for Idx<ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Idx({}: {:?})", self.index, self.name())
+    }
+}
+
+impl<ID> fmt::Display for Idx<ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+/// Serializes as the ID string, since indices depend on interning order.
+impl<ID> Serialize for Idx<ID> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.name().serialize(serializer)
+    }
+}
+
+impl<'de, ID> Deserialize<'de> for Idx<ID> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Idx::from_name(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn the_same_name_gets_the_same_handle() {
+        let before = interned_count();
+        let a = SpiritIdx::from_name("p1_1");
+        let b = SpiritIdx::from_name("p1_2");
+        assert_eq!(SpiritIdx::from_name("p1_1"), a);
+        assert_ne!(a, b);
+        assert_eq!(interned_count(), before + 2);
+        assert_eq!(a.name(), "p1_1");
+        assert_eq!(b.to_string(), "p1_2");
+    }
+
+    #[test]
+    fn handles_order_by_interning_not_by_name() {
+        let z = StarIdx::from_name("star_zxq");
+        let a = StarIdx::from_name("star_a1c");
+        assert!(z < a);
+        assert_eq!(a.index(), z.index() + 1);
+    }
+
+    #[test]
+    fn cast_keeps_the_handle() {
+        let before = interned_count();
+        let spirit = SpiritIdx::from_name("p2_3");
+        let entity: EntityIdx = spirit.cast();
+        assert_eq!(entity, EntityIdx::from_name("p2_3"));
+        assert_eq!(entity.cast::<SpiritID>(), spirit);
+        assert_eq!(interned_count(), before + 1);
+    }
+
+    #[test]
+    fn empty_and_unusual_names_are_interned_too() {
+        let empty = BaseIdx::from_name("");
+        let unicode = BaseIdx::from_name("bâse_☃");
+        assert_ne!(empty, unicode);
+        assert_eq!(empty.name(), "");
+        assert_eq!(unicode.name(), "bâse_☃");
+    }
+
+    #[test]
+    fn handles_are_hashable() {
+        let set: HashSet<OutpostIdx> = ["outpost_mdo", "outpost_x", "outpost_mdo"]
+            .iter()
+            .map(|name| OutpostIdx::from_name(name))
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&OutpostIdx::from_name("outpost_x")));
+    }
+
+    #[test]
+    fn serializes_as_the_name() {
+        // Interned first, so its index differs from what a fresh registry would give it.
+        SpiritIdx::from_name("p1_5");
+        let before = interned_count();
+        let idx = SpiritIdx::from_name("p2_1");
+        let json = serde_json::to_string(&idx).unwrap();
+        assert_eq!(json, r#""p2_1""#);
+        assert_eq!(serde_json::from_str::<SpiritIdx>(&json).unwrap(), idx);
+        let new: SpiritIdx = serde_json::from_str(r#""p2_9""#).unwrap();
+        assert_eq!(new.name(), "p2_9");
+        assert_eq!(interned_count(), before + 2);
+    }
+}
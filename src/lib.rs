@@ -14,29 +14,37 @@ compile_error!(
 #[macro_use]
 mod macros {
     macro_rules! try_can_from {
-        (impl TryFrom<$from:ident>, Error = $error:ident for $t:ty) => {
+        (impl TryFrom<$from:ident> for $t:ty) => {
             impl TryFrom<$from> for $t {
-                type Error = $error;
+                type Error = crate::ConversionError<$from>;
 
                 #[inline]
                 fn try_from(value: $from) -> Result<Self, Self::Error> {
-                    if <$t as CanFrom<$from>>::can_from(&value) {
-                        Ok(wasm_bindgen::JsCast::unchecked_into(value))
-                    } else {
-                        Err(wasm_bindgen::JsCast::unchecked_into(value))
+                    match <$t as CanFrom<$from>>::check(&value) {
+                        Ok(()) => Ok(wasm_bindgen::JsCast::unchecked_into(value)),
+                        Err(kind) => Err(crate::ConversionError::new(
+                            value,
+                            stringify!($from),
+                            stringify!($t),
+                            kind,
+                        )),
                     }
                 }
             }
 
             impl<'a> TryFrom<&'a $from> for &'a $t {
-                type Error = &'a $error;
+                type Error = crate::ConversionError<&'a $from>;
 
                 #[inline]
                 fn try_from(value: &'a $from) -> Result<Self, Self::Error> {
-                    if <$t as CanFrom<$from>>::can_from(value) {
-                        Ok(wasm_bindgen::JsCast::unchecked_ref(value))
-                    } else {
-                        Err(wasm_bindgen::JsCast::unchecked_ref(value))
+                    match <$t as CanFrom<$from>>::check(value) {
+                        Ok(()) => Ok(wasm_bindgen::JsCast::unchecked_ref(value)),
+                        Err(kind) => Err(crate::ConversionError::new(
+                            value,
+                            stringify!($from),
+                            stringify!($t),
+                            kind,
+                        )),
                     }
                 }
             }
@@ -45,36 +53,71 @@ mod macros {
 }
 
 pub mod base;
+pub mod budget;
+pub mod cache;
+pub mod canvas;
+pub mod color;
+pub mod commands;
 pub mod console;
+pub mod debug_draw;
+pub mod game;
 pub mod graphics;
+pub mod intern;
 pub mod memory;
 pub mod outpost;
 pub mod players;
+pub mod rules;
+mod shim;
+pub mod snapshot;
+pub mod spatial;
 pub mod spirit;
 pub mod star;
 
 #[cfg(feature = "RenderService")]
 pub mod render_service;
 
-use js_sys::{Array, Float64Array, JsString, Object, Reflect};
+#[cfg(feature = "sim")]
+pub mod sim;
+
+#[cfg(feature = "tracing")]
+pub mod profiler;
+
+use base::Base;
+use js_sys::{Array, JsString, Object, Reflect};
+use outpost::Outpost;
 use players::PlayerID;
-use spirit::{DeadSpirit, LivingEnemySpiritID, OperableSpiritID};
-use std::{convert::TryFrom, fmt::Debug, marker::PhantomData, ops::Deref};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spirit::{
+    LivingEnemySpirit, LivingEnemySpiritID, LivingSpirit, OperableSpirit, OperableSpiritID,
+};
+use star::Star;
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt::{self, Debug},
+    marker::PhantomData,
+    ops::{Add, AddAssign, Deref, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
 use wasm_bindgen::{prelude::*, JsCast};
 /// The most useful items to import.
 pub mod prelude {
     pub use crate::base::{base, bases, enemy_base, Base};
+    pub use crate::color::Color;
+    pub use crate::commands::Commands;
+    pub use crate::game::{Game, GameView};
     pub use crate::outpost::{outpost_mdo, outposts, Outpost};
     pub use crate::players::this_player_id;
+    pub use crate::snapshot::Snapshot;
     pub use crate::spirit::{
-        my_spirits, spirits, DeadFriendlySpirit, DeadFriendlySpiritID, LivingEnemySpirit,
-        LivingEnemySpiritID, LivingFriendlySpirit, LivingFriendlySpiritID, OperableSpirit,
-        OperableSpiritID, Spirit, SpiritID,
+        my_spirits, spirits, CircleSpirit, DeadFriendlySpirit, DeadFriendlySpiritID,
+        LivingEnemySpirit, LivingEnemySpiritID, LivingFriendlySpirit, LivingFriendlySpiritID,
+        OperableSpirit, OperableSpiritID, Spirit, SpiritID, SquareSpirit, TriangleSpirit,
+        TypedSpirit,
     };
     pub use crate::star::{star_a1c, star_p89, star_zxq, stars, Star};
     pub use crate::{
-        console, graphics, log, tick, Destructible, Entity, EntityID, EnumerateByID, GetByID,
-        OutpostSight, Position, Shape, Sight, TryGetByID,
+        console, current_tick, graphics, log, tick, Destructible, Entity, EntityID, EnumerateByID,
+        GetByID, OutpostSight, Position, Shape, Sight, TryGetByID, TypedStructure,
     };
 
     #[cfg(feature = "RenderService")]
@@ -82,106 +125,527 @@ pub mod prelude {
 }
 
 pub(crate) trait CanFrom<S: JsCast>: JsCast {
-    fn can_from(value: &S) -> bool;
+    /// Checks whether `value` is also a `Self`, and if not, why.
+    fn check(value: &S) -> Result<(), ConversionErrorKind>;
+
+    #[inline]
+    fn can_from(value: &S) -> bool {
+        Self::check(value).is_ok()
+    }
+}
+
+#[inline]
+pub(crate) fn require_property(
+    value: &JsValue,
+    property: &'static str,
+) -> Result<(), ConversionErrorKind> {
+    if Reflect::has(value, &property.into()).unwrap() {
+        Ok(())
+    } else {
+        Err(ConversionErrorKind::MissingProperty(property))
+    }
+}
+
+#[inline]
+pub(crate) fn reject_property(
+    value: &JsValue,
+    property: &'static str,
+) -> Result<(), ConversionErrorKind> {
+    if Reflect::has(value, &property.into()).unwrap() {
+        Err(ConversionErrorKind::UnexpectedProperty(property))
+    } else {
+        Ok(())
+    }
+}
+
+#[inline]
+pub(crate) fn require_structure_type(
+    value: &Structure,
+    expected: StructureType,
+) -> Result<(), ConversionErrorKind> {
+    let actual = value.structure_type();
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ConversionErrorKind::WrongStructureType { expected, actual })
+    }
+}
+
+#[inline]
+pub(crate) fn require_shape(
+    value: &Destructible,
+    expected: Shape,
+) -> Result<(), ConversionErrorKind> {
+    let actual = value.shape();
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ConversionErrorKind::WrongShape { expected, actual })
+    }
+}
+
+/// Why a [`TryFrom`] conversion between entity types failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConversionErrorKind {
+    /// The target type only holds living entities, but this one is dead.
+    Dead,
+    /// The target type only holds dead spirits, but this one is alive.
+    Alive,
+    /// The target type only holds your own spirits, but this one belongs to the enemy.
+    Enemy,
+    /// The target type only holds enemy spirits, but this one is yours.
+    Friendly,
+    /// The structure is of another [`StructureType`].
+    WrongStructureType {
+        expected: StructureType,
+        actual: StructureType,
+    },
+    /// The spirit is of another [`Shape`].
+    WrongShape { expected: Shape, actual: Shape },
+    /// The value lacks a property that all values of the target type have.
+    MissingProperty(&'static str),
+    /// The value has a property that no value of the target type has, e.g. it's a base, not a spirit.
+    UnexpectedProperty(&'static str),
+}
+
+impl fmt::Display for ConversionErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionErrorKind::Dead => write!(f, "it is dead"),
+            ConversionErrorKind::Alive => write!(f, "it is alive"),
+            ConversionErrorKind::Enemy => write!(f, "it belongs to the enemy"),
+            ConversionErrorKind::Friendly => write!(f, "it belongs to this player"),
+            ConversionErrorKind::WrongStructureType { expected, actual } => write!(
+                f,
+                "it is a {}, not a {}",
+                actual.to_str(),
+                expected.to_str()
+            ),
+            ConversionErrorKind::WrongShape { expected, actual } => write!(
+                f,
+                "it is one of the {}, not the {}",
+                actual.to_str(),
+                expected.to_str()
+            ),
+            ConversionErrorKind::MissingProperty(property) => {
+                write!(f, "it has no `{}` property", property)
+            }
+            ConversionErrorKind::UnexpectedProperty(property) => {
+                write!(f, "it has a `{}` property", property)
+            }
+        }
+    }
+}
+
+/// The error returned by failed [`TryFrom`] conversions between entity types.
+/// Holds on to the original value, so it can be recovered with [`into_value`](ConversionError::into_value).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionError<T> {
+    value: T,
+    from: &'static str,
+    to: &'static str,
+    kind: ConversionErrorKind,
+}
+
+impl<T> ConversionError<T> {
+    #[inline]
+    fn new(value: T, from: &'static str, to: &'static str, kind: ConversionErrorKind) -> Self {
+        ConversionError {
+            value,
+            from,
+            to,
+            kind,
+        }
+    }
+
+    /// The value that couldn't be converted.
+    #[inline]
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Gives back the value that couldn't be converted.
+    #[inline]
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// The name of the type converted from.
+    #[inline]
+    pub fn from_type(&self) -> &'static str {
+        self.from
+    }
+
+    /// The name of the type that the conversion was attempted to.
+    #[inline]
+    pub fn to_type(&self) -> &'static str {
+        self.to
+    }
+
+    /// Why the conversion failed.
+    #[inline]
+    pub fn kind(&self) -> ConversionErrorKind {
+        self.kind
+    }
 }
 
+impl<T> fmt::Display for ConversionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can't convert {} to {}: {}",
+            self.from, self.to, self.kind
+        )
+    }
+}
+
+impl<T: Debug> Error for ConversionError<T> {}
+
 /// A position on the game board. Ordered pair of [`f64`].
-pub struct Position(f64, f64);
+///
+/// `Position` doubles as a 2D vector: it supports the usual arithmetic operators
+/// (`+`, `-`, unary `-`, and scaling by an [`f64`]), as well as the geometry helpers below.
+/// Angles are in radians, measured counterclockwise from the positive x axis
+/// (which, since the game board's y axis points down, appears clockwise on screen).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Position {
+    /// The origin, `(0, 0)`.
+    pub const ZERO: Position = Position { x: 0.0, y: 0.0 };
+
+    /// Creates a new position from its coordinates.
+    #[inline]
+    pub const fn new(x: f64, y: f64) -> Self {
+        Position { x, y }
+    }
+
+    /// The unit vector pointing in the direction of `angle`.
+    #[inline]
+    pub fn from_angle(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Position::new(cos, sin)
+    }
+
+    /// Dot product of two vectors.
+    #[inline]
+    pub fn dot(self, other: Position) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product of two vectors (also known as the perp dot product).
+    #[inline]
+    pub fn cross(self, other: Position) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Squared length of the vector. Cheaper than [`length`](Position::length).
+    #[inline]
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    /// Length of the vector.
+    #[inline]
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// Squared distance between two positions. Cheaper than [`distance`](Position::distance).
+    #[inline]
+    pub fn distance_squared(self, other: Position) -> f64 {
+        (other - self).length_squared()
+    }
+
+    /// Distance between two positions.
+    #[inline]
+    pub fn distance(self, other: Position) -> f64 {
+        (other - self).length()
+    }
+
+    /// Returns the unit vector pointing in the same direction.
+    /// The zero vector is returned unchanged.
+    #[inline]
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self / length
+        }
+    }
+
+    /// Linear interpolation between `self` (for `t == 0`) and `other` (for `t == 1`).
+    #[inline]
+    pub fn lerp(self, other: Position, t: f64) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Rotates the vector by `angle` around the origin.
+    #[inline]
+    pub fn rotate(self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Position::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Rotates the position by `angle` around `center`.
+    #[inline]
+    pub fn rotate_around(self, center: Position, angle: f64) -> Self {
+        center + (self - center).rotate(angle)
+    }
+
+    /// Moves towards `target` by at most `max_dist`, without overshooting it.
+    #[inline]
+    pub fn move_towards(self, target: Position, max_dist: f64) -> Self {
+        let delta = target - self;
+        let dist = delta.length();
+        if dist <= max_dist || dist == 0.0 {
+            target
+        } else {
+            self + delta * (max_dist / dist)
+        }
+    }
+
+    /// The angle of the vector, in `(-π, π]`.
+    #[inline]
+    pub fn angle(self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    /// The angle of the direction from `self` towards `other`, in `(-π, π]`.
+    #[inline]
+    pub fn angle_to(self, other: Position) -> f64 {
+        (other - self).angle()
+    }
+
+    /// The signed angle to rotate `self` by to make it point in the same direction as `other`, in `(-π, π]`.
+    #[inline]
+    pub fn angle_between(self, other: Position) -> f64 {
+        self.cross(other).atan2(self.dot(other))
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    #[inline]
+    fn add(self, rhs: Position) -> Self::Output {
+        Position::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Position {
+    #[inline]
+    fn add_assign(&mut self, rhs: Position) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    #[inline]
+    fn sub(self, rhs: Position) -> Self::Output {
+        Position::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Position {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Position) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Position {
+    type Output = Position;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Position::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f64> for Position {
+    type Output = Position;
+
+    #[inline]
+    fn mul(self, rhs: f64) -> Self::Output {
+        Position::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Mul<Position> for f64 {
+    type Output = Position;
+
+    #[inline]
+    fn mul(self, rhs: Position) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl MulAssign<f64> for Position {
+    #[inline]
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<f64> for Position {
+    type Output = Position;
+
+    #[inline]
+    fn div(self, rhs: f64) -> Self::Output {
+        Position::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl DivAssign<f64> for Position {
+    #[inline]
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
 
 impl From<Position> for Vec<f64> {
     #[inline]
     fn from(pos: Position) -> Self {
-        vec![pos.0, pos.1]
+        vec![pos.x, pos.y]
     }
 }
 
 impl From<Vec<f64>> for Position {
     #[inline]
     fn from(vec: Vec<f64>) -> Self {
-        Position(vec[0], vec[1])
+        Position::new(vec[0], vec[1])
     }
 }
 
 impl From<Position> for (f64, f64) {
     #[inline]
     fn from(pos: Position) -> Self {
-        (pos.0, pos.1)
+        (pos.x, pos.y)
     }
 }
 
 impl From<(f64, f64)> for Position {
     #[inline]
     fn from(tuple: (f64, f64)) -> Self {
-        Position(tuple.0, tuple.1)
+        Position::new(tuple.0, tuple.1)
     }
 }
 
 impl From<Position> for [f64; 2] {
     #[inline]
     fn from(pos: Position) -> Self {
-        [pos.0, pos.1]
+        [pos.x, pos.y]
     }
 }
 
 impl From<[f64; 2]> for Position {
     #[inline]
     fn from(array: [f64; 2]) -> Self {
-        Position(array[0], array[1])
+        Position::new(array[0], array[1])
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.x, self.y].serialize(serializer)
     }
 }
 
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <[f64; 2]>::deserialize(deserializer).map(Position::from)
+    }
+}
+
+// Position marshalling
+//
+// Positions cross the JS boundary as `[x, y]` arrays. The crate's own bindings pass the coordinates as scalars
+// instead (see the `shim` module); these impls are for `extern` blocks outside the crate. Each conversion is a
+// single call to JS, which reads or writes both coordinates at once; passing a position in still has to build
+// an array, since that's what the callee expects.
+#[wasm_bindgen]
+extern "C" {
+    /// A JS `[x, y]` array.
+    #[wasm_bindgen(extends = Array, typescript_type = "Position")]
+    type PositionArray;
+}
+
 impl From<Position> for Array {
     #[inline]
     fn from(pos: Position) -> Self {
-        let float_arr = Float64Array::new_with_length(2);
-        float_arr.copy_from(&Vec::from(pos).into_boxed_slice());
-        Array::from(float_arr.as_ref())
+        shim::to_js(pos).unchecked_into()
     }
 }
 
 impl wasm_bindgen::convert::IntoWasmAbi for Position {
-    type Abi = <Vec<f64> as wasm_bindgen::convert::IntoWasmAbi>::Abi;
+    type Abi = <JsValue as wasm_bindgen::convert::IntoWasmAbi>::Abi;
 
     #[inline]
     fn into_abi(self) -> Self::Abi {
-        <Vec<f64>>::from(self).into_abi()
+        shim::to_js(self).into_abi()
     }
 }
 
 impl wasm_bindgen::convert::OptionIntoWasmAbi for Position {
     #[inline]
-    fn none() -> wasm_bindgen::convert::WasmSlice { wasm_bindgen::convert::WasmSlice { ptr: 0, len: 0 } }
+    fn none() -> Self::Abi {
+        <PositionArray as wasm_bindgen::convert::OptionIntoWasmAbi>::none()
+    }
 }
 
 impl wasm_bindgen::convert::FromWasmAbi for Position {
-    type Abi = <Box<[f64]> as wasm_bindgen::convert::FromWasmAbi>::Abi;
+    type Abi = <JsValue as wasm_bindgen::convert::FromWasmAbi>::Abi;
 
     #[inline]
     unsafe fn from_abi(js: Self::Abi) -> Self {
-        let slice = <Box<[f64]>>::from_abi(js);
-        Position(slice[0], slice[1])
+        shim::from_js(&JsValue::from_abi(js))
     }
 }
 
 impl wasm_bindgen::convert::OptionFromWasmAbi for Position {
     #[inline]
-    fn is_none(abi: &wasm_bindgen::convert::WasmSlice) -> bool { abi.ptr == 0 }
+    fn is_none(abi: &Self::Abi) -> bool {
+        <PositionArray as wasm_bindgen::convert::OptionFromWasmAbi>::is_none(abi)
+    }
 }
 
 impl wasm_bindgen::describe::WasmDescribe for Position {
     fn describe() {
-        <Box<[f64]>>::describe();
+        PositionArray::describe();
     }
 }
 
 /// The possible values of a spirit or base's [`shape`](Destructible::shape) property.
 #[wasm_bindgen(typescript_type = "Shape")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Shape {
     Circles = "circles",
     Squares = "squares",
     Triangles = "triangles",
 }
 
+impl Serialize for Shape {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_str().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shape {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Shape::from_str(&string).ok_or_else(|| {
+            serde::de::Error::unknown_variant(&string, &["circles", "squares", "triangles"])
+        })
+    }
+}
+
 // OutpostSight
 #[wasm_bindgen]
 extern "C" {
@@ -219,6 +683,76 @@ extern "C" {
     pub fn structures(this: &Sight) -> Vec<StructureID>;
 }
 
+thread_local! {
+    /// Looks up the entities with the IDs in `sight[key]`, skipping any that no longer exist.
+    static RESOLVE_SIGHT: js_sys::Function = js_sys::Function::new_with_args(
+        "sight, key",
+        r#"
+        const entities = [];
+        for (const id of sight[key]) {
+            const entity = __yareio_sys.entity(id);
+            if (entity) entities.push(entity);
+        }
+        return entities;
+        "#,
+    );
+}
+
+#[inline]
+pub(crate) fn resolve_sight<T: JsCast>(sight: &OutpostSight, key: &str) -> Vec<T> {
+    shim::install();
+    let entities: Array = RESOLVE_SIGHT
+        .with(|resolve| resolve.call2(&JsValue::NULL, sight, &key.into()))
+        .unwrap()
+        .unchecked_into();
+    entities.iter().map(JsCast::unchecked_into).collect()
+}
+
+impl OutpostSight {
+    /// The [`enemies`](OutpostSight::enemies), looked up in a single call to JS.
+    ///
+    /// An outpost's enemies are the spirits of whoever doesn't control it, so they're only enemy spirits if you
+    /// do. See [`Outpost::threatened_spirits`](crate::outpost::Outpost::threatened_spirits) for when you don't.
+    #[inline]
+    pub fn enemies_entities(&self) -> Vec<LivingSpirit> {
+        resolve_sight(self, "enemies")
+    }
+}
+
+impl Sight {
+    /// The [`enemies`](OutpostSight::enemies), looked up in a single call to JS.
+    #[inline]
+    pub fn enemies_entities(&self) -> Vec<LivingEnemySpirit> {
+        resolve_sight(self, "enemies")
+    }
+
+    /// The [`friends`](Sight::friends), looked up in a single call to JS.
+    #[inline]
+    pub fn friends_entities(&self) -> Vec<OperableSpirit> {
+        resolve_sight(self, "friends")
+    }
+
+    /// The [`friends_beamable`](Sight::friends_beamable), looked up in a single call to JS.
+    #[inline]
+    pub fn friends_beamable_entities(&self) -> Vec<OperableSpirit> {
+        resolve_sight(self, "friends_beamable")
+    }
+
+    /// The [`enemies_beamable`](Sight::enemies_beamable), looked up in a single call to JS.
+    #[inline]
+    pub fn enemies_beamable_entities(&self) -> Vec<LivingEnemySpirit> {
+        resolve_sight(self, "enemies_beamable")
+    }
+
+    /// The [`structures`](Sight::structures), looked up in a single call to JS.
+    pub fn structure_entities(&self) -> Vec<TypedStructure> {
+        resolve_sight::<Structure>(self, "structures")
+            .into_iter()
+            .map(TypedStructure::from)
+            .collect()
+    }
+}
+
 // Entity
 #[wasm_bindgen]
 extern "C" {
@@ -237,9 +771,6 @@ extern "C" {
     #[wasm_bindgen(method, getter)]
     pub fn id(this: &Entity) -> EntityID;
 
-    #[wasm_bindgen(method, getter)]
-    pub fn position(this: &Entity) -> Position;
-
     #[wasm_bindgen(method, getter)]
     pub fn size(this: &Entity) -> u32;
 
@@ -254,6 +785,11 @@ extern "C" {
 }
 
 impl Entity {
+    #[inline]
+    pub fn position(&self) -> Position {
+        shim::entity_position(self)
+    }
+
     #[inline]
     pub fn last_energized(&self) -> Option<EntityID> {
         let jsval = self._last_energized();
@@ -287,13 +823,18 @@ extern "C" {
 
 impl CanFrom<Entity> for LivingEntity {
     #[inline]
-    fn can_from(value: &Entity) -> bool {
-        !Reflect::has(value, &"hp".into()).unwrap()
-            || value.unchecked_ref::<Destructible>().hp() > 0
+    fn check(value: &Entity) -> Result<(), ConversionErrorKind> {
+        if Reflect::has(value, &"hp".into()).unwrap()
+            && value.unchecked_ref::<Destructible>().hp() <= 0
+        {
+            Err(ConversionErrorKind::Dead)
+        } else {
+            Ok(())
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Entity>, Error = DeadSpirit for LivingEntity);
+try_can_from!(impl TryFrom<Entity> for LivingEntity);
 
 // Destructible
 #[wasm_bindgen]
@@ -331,12 +872,12 @@ extern "C" {
 
 impl CanFrom<Entity> for Destructible {
     #[inline]
-    fn can_from(value: &Entity) -> bool {
-        Reflect::has(value, &"hp".into()).unwrap()
+    fn check(value: &Entity) -> Result<(), ConversionErrorKind> {
+        require_property(value, "hp")
     }
 }
 
-try_can_from!(impl TryFrom<Entity>, Error = Entity for Destructible);
+try_can_from!(impl TryFrom<Entity> for Destructible);
 
 // LivingDesctructible
 #[wasm_bindgen]
@@ -359,26 +900,31 @@ extern "C" {
 
 impl CanFrom<Destructible> for LivingDestructible {
     #[inline]
-    fn can_from(value: &Destructible) -> bool {
-        value.hp() > 0
+    fn check(value: &Destructible) -> Result<(), ConversionErrorKind> {
+        if value.hp() > 0 {
+            Ok(())
+        } else {
+            Err(ConversionErrorKind::Dead)
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Destructible>, Error = Destructible for LivingDestructible);
+try_can_from!(impl TryFrom<Destructible> for LivingDestructible);
 
 impl CanFrom<LivingEntity> for LivingDestructible {
     #[inline]
-    fn can_from(value: &LivingEntity) -> bool {
-        Reflect::has(value, &"hp".into()).unwrap()
+    fn check(value: &LivingEntity) -> Result<(), ConversionErrorKind> {
+        require_property(value, "hp")
     }
 }
 
-try_can_from!(impl TryFrom<LivingEntity>, Error = LivingEntity for LivingDestructible);
+try_can_from!(impl TryFrom<LivingEntity> for LivingDestructible);
 
 /// The possible [`structure_type`](Structure::structure_type)s.
 ///
 /// [Yare.io Documentation](https://yare.io/documentation)
 #[wasm_bindgen(typescript_type = "StructureType")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum StructureType {
     Base = "base",
     Outpost = "outpost",
@@ -412,21 +958,81 @@ extern "C" {
 
 impl CanFrom<Entity> for Structure {
     #[inline]
-    fn can_from(value: &Entity) -> bool {
-        Reflect::has(value, &"structure_type".into()).unwrap()
+    fn check(value: &Entity) -> Result<(), ConversionErrorKind> {
+        require_property(value, "structure_type")
     }
 }
 
-try_can_from!(impl TryFrom<Entity>, Error = Entity for Structure);
+try_can_from!(impl TryFrom<Entity> for Structure);
 
 impl CanFrom<LivingEntity> for Structure {
     #[inline]
-    fn can_from(value: &LivingEntity) -> bool {
-        <Structure as CanFrom<Entity>>::can_from(value)
+    fn check(value: &LivingEntity) -> Result<(), ConversionErrorKind> {
+        <Structure as CanFrom<Entity>>::check(value)
+    }
+}
+
+try_can_from!(impl TryFrom<LivingEntity> for Structure);
+
+/// A [`Structure`], as its specific type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypedStructure {
+    Base(Base),
+    Outpost(Outpost),
+    Star(Star),
+    /// A structure whose type this crate doesn't know about.
+    Unknown(Structure),
+}
+
+impl TypedStructure {
+    #[inline]
+    pub fn structure_type(&self) -> StructureType {
+        match self {
+            TypedStructure::Base(_) => StructureType::Base,
+            TypedStructure::Outpost(_) => StructureType::Outpost,
+            TypedStructure::Star(_) => StructureType::Star,
+            TypedStructure::Unknown(structure) => structure.structure_type(),
+        }
+    }
+}
+
+impl From<Structure> for TypedStructure {
+    #[inline]
+    fn from(structure: Structure) -> Self {
+        match structure.structure_type() {
+            StructureType::Base => TypedStructure::Base(structure.unchecked_into()),
+            StructureType::Outpost => TypedStructure::Outpost(structure.unchecked_into()),
+            StructureType::Star => TypedStructure::Star(structure.unchecked_into()),
+            _ => TypedStructure::Unknown(structure),
+        }
     }
 }
 
-try_can_from!(impl TryFrom<LivingEntity>, Error = Entity for Structure);
+impl From<TypedStructure> for Structure {
+    #[inline]
+    fn from(structure: TypedStructure) -> Self {
+        match structure {
+            TypedStructure::Base(base) => base.into(),
+            TypedStructure::Outpost(outpost) => outpost.into(),
+            TypedStructure::Star(star) => star.into(),
+            TypedStructure::Unknown(structure) => structure,
+        }
+    }
+}
+
+impl Deref for TypedStructure {
+    type Target = Structure;
+
+    #[inline]
+    fn deref(&self) -> &Structure {
+        match self {
+            TypedStructure::Base(base) => base,
+            TypedStructure::Outpost(outpost) => outpost,
+            TypedStructure::Star(star) => star,
+            TypedStructure::Unknown(structure) => structure,
+        }
+    }
+}
 
 // GetById
 
@@ -471,32 +1077,70 @@ impl<ID: JsCast, V: JsCast, T: GetByID<ID, V>> TryGetByID<ID, V> for T {}
 pub trait EnumerateByID<ID: JsCast, V: JsCast>: GetByID<ID, V> {
     // An iterator visiting all IDs.
     fn ids(&self) -> ArrayTypedIter<ID> {
-        let array = Object::keys(self);
-        ArrayTypedIter::<ID> {
-            range: 0..array.length(),
-            array,
-            phantom: PhantomData,
-        }
+        ArrayTypedIter::new(Object::keys(self))
     }
 
     // An iterator visiting all values.
     fn values(&self) -> ArrayTypedIter<V> {
-        let array = Object::values(self);
-        ArrayTypedIter::<V> {
-            range: 0..array.length(),
-            array,
+        ArrayTypedIter::new(Object::values(self))
+    }
+
+    // An iterator visiting all IDs and their values, with a single enumeration.
+    fn entries(&self) -> ArrayEntriesIter<ID, V> {
+        ArrayEntriesIter {
+            inner: ArrayTypedIter::new(Object::entries(self)),
             phantom: PhantomData,
         }
     }
 }
 
 /// Iterator returned by [`EnumerateByID`]'s methods.
+///
+/// Cloning it is cheap: clones share the enumerated JS array, so one enumeration can be walked several times.
 pub struct ArrayTypedIter<T: JsCast> {
     range: std::ops::Range<u32>,
     array: Array,
     phantom: PhantomData<T>,
 }
 
+impl<T: JsCast> ArrayTypedIter<T> {
+    /// Iterates over `array`, whose elements must all be `T`s.
+    #[inline]
+    pub(crate) fn new(array: Array) -> Self {
+        ArrayTypedIter {
+            range: 0..array.length(),
+            array,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Rewinds the iterator to the start of the enumeration, without enumerating again.
+    #[inline]
+    pub fn rewind(&mut self) {
+        self.range = 0..self.array.length();
+    }
+}
+
+impl<T: JsCast> Clone for ArrayTypedIter<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ArrayTypedIter {
+            range: self.range.clone(),
+            array: self.array.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: JsCast> Debug for ArrayTypedIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayTypedIter")
+            .field("range", &self.range)
+            .field("array", &self.array)
+            .finish()
+    }
+}
+
 impl<T: JsCast> std::iter::Iterator for ArrayTypedIter<T> {
     type Item = T;
 
@@ -509,6 +1153,22 @@ impl<T: JsCast> std::iter::Iterator for ArrayTypedIter<T> {
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.range.size_hint()
     }
+
+    /// Skips the first `n` elements without fetching them from JS.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let index = self.range.nth(n)?;
+        Some(self.array.get(index).unchecked_into())
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.range.len()
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
 }
 
 impl<T: JsCast> std::iter::DoubleEndedIterator for ArrayTypedIter<T> {
@@ -516,16 +1176,222 @@ impl<T: JsCast> std::iter::DoubleEndedIterator for ArrayTypedIter<T> {
         let index = self.range.next_back()?;
         Some(self.array.get(index).unchecked_into())
     }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        let index = self.range.nth_back(n)?;
+        Some(self.array.get(index).unchecked_into())
+    }
 }
 impl<T: JsCast> std::iter::FusedIterator for ArrayTypedIter<T> {}
 
 impl<T: JsCast> std::iter::ExactSizeIterator for ArrayTypedIter<T> {}
 
+/// Iterator returned by [`EnumerateByID::entries`]. Cloning it is cheap, like [`ArrayTypedIter`].
+pub struct ArrayEntriesIter<K: JsCast, V: JsCast> {
+    inner: ArrayTypedIter<Array>,
+    phantom: PhantomData<(K, V)>,
+}
+
+impl<K: JsCast, V: JsCast> ArrayEntriesIter<K, V> {
+    /// Rewinds the iterator to the start of the enumeration, without enumerating again.
+    #[inline]
+    pub fn rewind(&mut self) {
+        self.inner.rewind();
+    }
+
+    #[inline]
+    fn split(entry: Array) -> (K, V) {
+        (entry.get(0).unchecked_into(), entry.get(1).unchecked_into())
+    }
+}
+
+impl<K: JsCast, V: JsCast> Clone for ArrayEntriesIter<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ArrayEntriesIter {
+            inner: self.inner.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<K: JsCast, V: JsCast> Debug for ArrayEntriesIter<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayEntriesIter")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: JsCast, V: JsCast> std::iter::Iterator for ArrayEntriesIter<K, V> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(Self::split)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth(n).map(Self::split)
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.inner.count()
+    }
+
+    #[inline]
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(Self::split)
+    }
+}
+
+impl<K: JsCast, V: JsCast> std::iter::DoubleEndedIterator for ArrayEntriesIter<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(Self::split)
+    }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth_back(n).map(Self::split)
+    }
+}
+
+impl<K: JsCast, V: JsCast> std::iter::FusedIterator for ArrayEntriesIter<K, V> {}
+
+impl<K: JsCast, V: JsCast> std::iter::ExactSizeIterator for ArrayEntriesIter<K, V> {}
+
 // `tick`
 #[wasm_bindgen]
 extern "C" {
     /// `tick` (the number of ticks since the start of the game).
-
+    ///
+    /// The value is read from JS the first time it's used and kept afterwards, so it goes stale if the module
+    /// outlives a tick. Use [`current_tick`] to compare ticks.
     #[wasm_bindgen]
     pub static tick: u32;
 }
+
+/// The current value of [`tick`], read from JS on every call.
+#[inline]
+pub fn current_tick() -> u32 {
+    shim::tick()
+}
+
+// `performance.now()`
+#[wasm_bindgen]
+extern "C" {
+    /// `performance.now()`: a high-resolution timestamp, in milliseconds.
+    #[wasm_bindgen(js_namespace = performance, js_name = "now")]
+    pub fn now() -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn assert_near(a: Position, b: Position) {
+        assert!(a.distance(b) < 1e-9, "{:?} is not near {:?}", a, b);
+    }
+
+    #[test]
+    fn position_arithmetic() {
+        let a = Position::new(1.0, 2.0);
+        let b = Position::new(3.0, -4.0);
+        assert_eq!(a + b, Position::new(4.0, -2.0));
+        assert_eq!(a - b, Position::new(-2.0, 6.0));
+        assert_eq!(-a, Position::new(-1.0, -2.0));
+        assert_eq!(a * 2.0, Position::new(2.0, 4.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(b / 2.0, Position::new(1.5, -2.0));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        c *= 3.0;
+        c /= 3.0;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn position_products_and_lengths() {
+        let a = Position::new(3.0, 4.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.length_squared(), 25.0);
+        assert_eq!(a.dot(Position::new(-4.0, 3.0)), 0.0);
+        assert_eq!(Position::new(1.0, 0.0).cross(Position::new(0.0, 1.0)), 1.0);
+        assert_eq!(Position::ZERO.distance(a), 5.0);
+        assert_eq!(a.distance_squared(Position::ZERO), 25.0);
+        assert_eq!(a.distance(a), 0.0);
+    }
+
+    #[test]
+    fn normalize_keeps_the_zero_vector() {
+        assert_eq!(Position::ZERO.normalize(), Position::ZERO);
+        assert_eq!(
+            Position::new(0.0, -7.0).normalize(),
+            Position::new(0.0, -1.0)
+        );
+        assert!((Position::new(3.0, 4.0).normalize().length() - 1.0).abs() < 1e-12);
+        // Tiny vectors don't underflow to zero length.
+        assert!((Position::new(1e-300, 1e-300).normalize().length() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rotate_and_angles() {
+        let x = Position::new(1.0, 0.0);
+        assert_near(x.rotate(FRAC_PI_2), Position::new(0.0, 1.0));
+        assert_near(x.rotate(PI), Position::new(-1.0, 0.0));
+        assert_near(x.rotate(2.0 * PI), x);
+        assert_near(
+            Position::new(2.0, 1.0).rotate_around(Position::new(1.0, 1.0), -FRAC_PI_2),
+            Position::new(1.0, 0.0),
+        );
+        assert_eq!(Position::new(-1.0, 0.0).angle(), PI);
+        assert_eq!(Position::ZERO.angle(), 0.0);
+        assert_eq!(x.angle_to(Position::new(1.0, 5.0)), FRAC_PI_2);
+        assert!((x.angle_between(Position::new(0.0, -3.0)) + FRAC_PI_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn from_angle_is_a_unit_vector() {
+        assert_near(Position::from_angle(0.0), Position::new(1.0, 0.0));
+        assert_near(Position::from_angle(-FRAC_PI_2), Position::new(0.0, -1.0));
+        for i in 0..16 {
+            let angle = f64::from(i) * PI / 8.0 - PI;
+            let unit = Position::from_angle(angle);
+            assert!((unit.length() - 1.0).abs() < 1e-12);
+            assert_near(Position::from_angle(unit.angle()), unit);
+        }
+    }
+
+    #[test]
+    fn lerp_and_move_towards() {
+        let a = Position::new(0.0, 0.0);
+        let b = Position::new(10.0, 0.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Position::new(2.5, 0.0));
+        assert_eq!(a.move_towards(b, 4.0), Position::new(4.0, 0.0));
+        assert_eq!(a.move_towards(b, 20.0), b);
+        assert_eq!(b.move_towards(b, 1.0), b);
+    }
+
+    #[test]
+    fn position_conversions() {
+        let a = Position::new(1.5, -2.0);
+        assert_eq!(Position::from(<[f64; 2]>::from(a)), a);
+        assert_eq!(Position::from(<(f64, f64)>::from(a)), a);
+        assert_eq!(Position::from(Vec::<f64>::from(a)), a);
+        assert_eq!(serde_json::to_string(&a).unwrap(), "[1.5,-2.0]");
+        assert_eq!(serde_json::from_str::<Position>("[1.5,-2]").unwrap(), a);
+    }
+}
@@ -1,5 +1,10 @@
-///! Provides access to properties of the `memory` object.
-///! Consider replacing with bindings that fit your own usage.
+//! Provides access to properties of the `memory` object.
+//!
+//! [`get`], [`set`] and [`delete`] work on raw [`JsValue`]s. [`MemoryKey`] stores typed values instead,
+//! encoding them with `serde`, and [`migrate`] keeps stored data in step with the code that reads it.
+use js_sys::JSON;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{error::Error, fmt, marker::PhantomData};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -37,3 +42,197 @@ pub fn set(prop: &JsValue, val: &JsValue) {
 pub fn delete(prop: &JsValue) {
     memory.delete(prop)
 }
+
+/// A typed handle to `memory[name]`.
+///
+/// Values are stored as plain JS objects, so they stay readable from JS and from the console.
+///
+/// ```ignore
+/// static PHASE: MemoryKey<Phase> = MemoryKey::new("phase");
+///
+/// let phase = PHASE.get_or_default()?;
+/// PHASE.set(&Phase::Attack)?;
+/// ```
+pub struct MemoryKey<T> {
+    name: &'static str,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> MemoryKey<T> {
+    #[inline]
+    pub const fn new(name: &'static str) -> Self {
+        MemoryKey {
+            name,
+            marker: PhantomData,
+        }
+    }
+
+    /// The property of `memory` this key refers to.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether `memory[name]` is set.
+    #[inline]
+    pub fn exists(&self) -> bool {
+        !get(&self.name.into()).is_undefined()
+    }
+
+    /// Delete `memory[name]`.
+    #[inline]
+    pub fn delete(&self) {
+        delete(&self.name.into())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> MemoryKey<T> {
+    /// Decodes `memory[name]`, or returns `None` if it isn't set.
+    pub fn get(&self) -> Result<Option<T>, MemoryError> {
+        let value = get(&self.name.into());
+        if value.is_undefined() {
+            return Ok(None);
+        }
+        let json = JSON::stringify(&value).map_err(MemoryError::Js)?;
+        serde_json::from_str(&String::from(json))
+            .map(Some)
+            .map_err(|source| MemoryError::Decode {
+                key: self.name,
+                source,
+            })
+    }
+
+    /// Encodes `value` into `memory[name]`.
+    pub fn set(&self, value: &T) -> Result<(), MemoryError> {
+        let json = serde_json::to_string(value).map_err(|source| MemoryError::Encode {
+            key: self.name,
+            source,
+        })?;
+        let value = JSON::parse(&json).map_err(MemoryError::Js)?;
+        set(&self.name.into(), &value);
+        Ok(())
+    }
+
+    /// Decodes `memory[name]`, or returns the default value if it isn't set.
+    pub fn get_or_default(&self) -> Result<T, MemoryError>
+    where
+        T: Default,
+    {
+        Ok(self.get()?.unwrap_or_default())
+    }
+
+    /// Decodes `memory[name]` (or the default value if it isn't set), modifies it with `f`, and stores it back.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, MemoryError>
+    where
+        T: Default,
+    {
+        let mut value = self.get_or_default()?;
+        let result = f(&mut value);
+        self.set(&value)?;
+        Ok(result)
+    }
+}
+
+impl<T> Clone for MemoryKey<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for MemoryKey<T> {}
+
+impl<T> fmt::Debug for MemoryKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MemoryKey").field(&self.name).finish()
+    }
+}
+
+/// The key the schema version is stored under.
+const SCHEMA_VERSION: MemoryKey<u32> = MemoryKey::new("__schema_version");
+
+/// The version of the data in `memory`, as recorded by [`migrate`].
+/// Memory that was never migrated is at version 0.
+pub fn schema_version() -> Result<u32, MemoryError> {
+    Ok(SCHEMA_VERSION.get()?.unwrap_or(0))
+}
+
+/// Brings `memory` up to schema `version`.
+///
+/// For each version `from` between the stored [`schema_version`] and `version`, calls `step(from)`, which should
+/// convert the data from version `from` to version `from + 1`. The version is recorded after every step, so a
+/// failed migration resumes where it stopped. Call this at the start of every tick; it's a single read once
+/// memory is up to date.
+///
+/// Fails with [`MemoryError::UnsupportedVersion`] if memory was written by a newer schema, and with
+/// [`MemoryError::Migration`] if a step does. Steps can fail with any error, `?` included, or with a message:
+/// `Err("no such unit".into())`.
+pub fn migrate(
+    version: u32,
+    mut step: impl FnMut(u32) -> Result<(), Box<dyn Error>>,
+) -> Result<(), MemoryError> {
+    let found = schema_version()?;
+    if found > version {
+        return Err(MemoryError::UnsupportedVersion { found, version });
+    }
+    for from in found..version {
+        step(from).map_err(|source| MemoryError::Migration { from, source })?;
+        SCHEMA_VERSION.set(&(from + 1))?;
+    }
+    Ok(())
+}
+
+/// An error reading or writing typed values in `memory`.
+#[derive(Debug)]
+pub enum MemoryError {
+    /// The value couldn't be serialized.
+    Encode {
+        key: &'static str,
+        source: serde_json::Error,
+    },
+    /// The stored value doesn't match the expected type.
+    Decode {
+        key: &'static str,
+        source: serde_json::Error,
+    },
+    /// `JSON.parse` or `JSON.stringify` threw, e.g. because the stored value has cycles.
+    Js(JsValue),
+    /// `memory` was written by a newer schema version than the one migrated to.
+    UnsupportedVersion { found: u32, version: u32 },
+    /// The [`migrate`] step from schema version `from` failed.
+    Migration { from: u32, source: Box<dyn Error> },
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::Encode { key, source } => {
+                write!(f, "can't encode memory[{:?}]: {}", key, source)
+            }
+            MemoryError::Decode { key, source } => {
+                write!(f, "can't decode memory[{:?}]: {}", key, source)
+            }
+            MemoryError::Js(error) => write!(f, "JS error: {:?}", error),
+            MemoryError::UnsupportedVersion { found, version } => write!(
+                f,
+                "memory has schema version {}, newer than {}",
+                found, version
+            ),
+            MemoryError::Migration { from, source } => write!(
+                f,
+                "migration from schema version {} failed: {}",
+                from, source
+            ),
+        }
+    }
+}
+
+impl Error for MemoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MemoryError::Encode { source, .. } | MemoryError::Decode { source, .. } => Some(source),
+            MemoryError::Migration { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Provides access to outposts.
 
 use crate::players::PlayerID;
-use crate::{prelude::*, CanFrom};
-use crate::{Structure, StructureID};
+use crate::rules::rules;
+use crate::snapshot::OutpostSnapshot;
+use crate::{prelude::*, require_structure_type, CanFrom, ConversionErrorKind};
+use crate::{resolve_sight, Structure, StructureID, StructureType};
 use js_sys::Object;
 use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
@@ -37,12 +39,12 @@ extern "C" {
 
 impl CanFrom<Structure> for Outpost {
     #[inline]
-    fn can_from(value: &Structure) -> bool {
-        value.structure_type().to_str() == "outpost"
+    fn check(value: &Structure) -> Result<(), ConversionErrorKind> {
+        require_structure_type(value, StructureType::Outpost)
     }
 }
 
-try_can_from!(impl TryFrom<Structure>, Error = Structure for Outpost);
+try_can_from!(impl TryFrom<Structure> for Outpost);
 
 // `outposts`
 #[wasm_bindgen]
@@ -82,3 +84,260 @@ extern "C" {
     #[wasm_bindgen]
     pub static outpost: Outpost;
 }
+
+impl Outpost {
+    /// Your living spirits that the outpost can currently shoot at: its
+    /// [`enemies`](OutpostSight::enemies), if the enemy controls it. Looked up in a single call to JS.
+    pub fn threatened_spirits(&self) -> Vec<OperableSpirit> {
+        let control = self.control();
+        if control.length() == 0 || &control == this_player_id.as_ref() {
+            return Vec::new();
+        }
+        resolve_sight(&self.sight(), "enemies")
+    }
+}
+
+// OutpostModel
+
+/// Predicts an outpost's range, damage, and how it changes hands, under the current [`rules`].
+///
+/// A model read from the game starts out with the outpost's actual [`range`](Outpost::range); the rules are only
+/// used once the model's energy differs from what it was read at.
+///
+/// ```ignore
+/// let model = OutpostModel::from(&*outpost_mdo);
+/// if let Some(capture) = model.capture(&this_player_id.as_string().unwrap(), 40) {
+///     // Worth it?
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutpostModel {
+    pub position: Position,
+    pub energy: i32,
+    pub energy_capacity: i32,
+    /// The ID of the controlling player, or an empty string if the outpost is neutral.
+    pub control: String,
+    /// The range the game reported, and the outpost's energy at the time.
+    pub observed_range: Option<(i32, f64)>,
+}
+
+/// What capturing an outpost takes, as predicted by [`OutpostModel::capture`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Capture {
+    /// Ticks of energizing until control flips.
+    pub ticks: u32,
+    /// Energy given to the outpost over those ticks.
+    pub energy: i32,
+    /// Energy the outpost takes from the capturing spirits by shooting them in the meantime.
+    pub damage: i32,
+}
+
+impl OutpostModel {
+    #[inline]
+    pub fn is_neutral(&self) -> bool {
+        self.control.is_empty()
+    }
+
+    /// The [`observed_range`](OutpostModel::observed_range), if it's still current.
+    #[inline]
+    fn current_observed_range(&self) -> Option<f64> {
+        match self.observed_range {
+            Some((energy, range)) if energy == self.energy => Some(range),
+            _ => None,
+        }
+    }
+
+    /// Whether the outpost has enough energy for its empowered range and damage.
+    #[inline]
+    pub fn is_empowered(&self) -> bool {
+        match self.current_observed_range() {
+            Some(range) => range > rules().outpost_range,
+            None => self.energy >= rules().outpost_empowered_energy,
+        }
+    }
+
+    /// How far the outpost shoots.
+    #[inline]
+    pub fn range(&self) -> f64 {
+        self.current_observed_range()
+            .unwrap_or_else(|| rules().outpost_range(self.energy))
+    }
+
+    /// The energy the outpost takes from the spirit it shoots each tick, or 0 if it can't shoot: it's neutral or
+    /// out of energy.
+    pub fn damage(&self) -> i32 {
+        if self.is_neutral() || self.energy <= 0 {
+            0
+        } else if self.is_empowered() {
+            rules().outpost_empowered_damage
+        } else {
+            rules().outpost_damage
+        }
+    }
+
+    /// Whether the outpost would shoot at a spirit of `player_id`'s at `pos`.
+    pub fn threatens(&self, player_id: &str, pos: Position) -> bool {
+        self.damage() > 0
+            && self.control != player_id
+            && self.position.distance(pos) <= self.range()
+    }
+
+    /// What it takes for `player_id` to gain control of the outpost, energizing it with `energy_per_tick` in total
+    /// each tick. The outpost is assumed to shoot the capturing spirits every tick it can.
+    /// `None` if `player_id` already controls it, or if `energy_per_tick` isn't positive.
+    pub fn capture(&self, player_id: &str, energy_per_tick: i32) -> Option<Capture> {
+        if self.control == player_id || energy_per_tick <= 0 {
+            return None;
+        }
+        if self.is_neutral() {
+            // The first energize takes control.
+            return Some(Capture {
+                ticks: 1,
+                energy: energy_per_tick,
+                damage: 0,
+            });
+        }
+        let mut model = self.clone();
+        let mut capture = Capture {
+            ticks: 0,
+            energy: 0,
+            damage: 0,
+        };
+        loop {
+            capture.ticks += 1;
+            capture.energy += energy_per_tick;
+            // Energizing an enemy outpost takes twice the energy given.
+            model.energy -= 2 * energy_per_tick;
+            if model.energy <= 0 {
+                return Some(capture);
+            }
+            capture.damage += model.damage();
+            model.energy -= 1;
+        }
+    }
+}
+
+impl From<&Outpost> for OutpostModel {
+    fn from(value: &Outpost) -> Self {
+        let energy = value.energy();
+        OutpostModel {
+            position: value.position(),
+            energy,
+            energy_capacity: value.energy_capacity(),
+            control: value.control().as_string().unwrap_or_default(),
+            observed_range: Some((energy, value.range())),
+        }
+    }
+}
+
+impl From<&OutpostSnapshot> for OutpostModel {
+    fn from(value: &OutpostSnapshot) -> Self {
+        OutpostModel {
+            position: value.position,
+            energy: value.energy,
+            energy_capacity: value.energy_capacity,
+            control: value.control.clone(),
+            observed_range: Some((value.energy, value.range)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(energy: i32, control: &str) -> OutpostModel {
+        OutpostModel {
+            position: Position::new(0.0, 0.0),
+            energy,
+            energy_capacity: 1000,
+            control: control.to_owned(),
+            observed_range: None,
+        }
+    }
+
+    #[test]
+    fn range_and_damage_go_up_at_the_empowered_energy() {
+        let rules = rules();
+        let below = model(rules.outpost_empowered_energy - 1, "p2");
+        assert!(!below.is_empowered());
+        assert_eq!(below.range(), rules.outpost_range);
+        assert_eq!(below.damage(), rules.outpost_damage);
+
+        let at = model(rules.outpost_empowered_energy, "p2");
+        assert!(at.is_empowered());
+        assert_eq!(at.range(), rules.outpost_empowered_range);
+        assert_eq!(at.damage(), rules.outpost_empowered_damage);
+    }
+
+    #[test]
+    fn neutral_or_empty_outposts_do_no_damage() {
+        assert_eq!(model(100, "").damage(), 0);
+        assert_eq!(model(0, "p2").damage(), 0);
+        assert!(!model(0, "p2").threatens("p1", Position::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn observed_range_wins_until_the_energy_changes() {
+        let mut observed = model(100, "p2");
+        observed.observed_range = Some((100, 650.0));
+        assert_eq!(observed.range(), 650.0);
+        assert!(observed.is_empowered());
+        assert_eq!(observed.damage(), rules().outpost_empowered_damage);
+
+        observed.energy = 99;
+        assert_eq!(observed.range(), rules().outpost_range);
+        assert_eq!(observed.damage(), rules().outpost_damage);
+    }
+
+    #[test]
+    fn threatens_only_other_players_in_range() {
+        let model = model(100, "p2");
+        let range = model.range();
+        assert!(model.threatens("p1", Position::new(range, 0.0)));
+        assert!(!model.threatens("p1", Position::new(range + 1.0, 0.0)));
+        assert!(!model.threatens("p2", Position::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn capture_of_a_neutral_outpost_takes_one_tick() {
+        assert_eq!(
+            model(0, "").capture("p1", 10),
+            Some(Capture {
+                ticks: 1,
+                energy: 10,
+                damage: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn capture_is_none_for_your_own_outpost_or_no_energy() {
+        assert_eq!(model(100, "p1").capture("p1", 10), None);
+        assert_eq!(model(100, "p2").capture("p1", 0), None);
+    }
+
+    #[test]
+    fn capture_drains_twice_the_energy_given_and_counts_damage() {
+        // 100 -> 40, shoots, 39 -> flipped.
+        assert_eq!(
+            model(100, "p2").capture("p1", 30),
+            Some(Capture {
+                ticks: 2,
+                energy: 60,
+                damage: rules().outpost_damage,
+            })
+        );
+
+        // 800 -> 600 (empowered), 599 -> 399, 398 -> 198, 197 -> flipped.
+        let rules = rules();
+        assert_eq!(
+            model(800, "p2").capture("p1", 100),
+            Some(Capture {
+                ticks: 4,
+                energy: 400,
+                damage: rules.outpost_empowered_damage + 2 * rules.outpost_damage,
+            })
+        );
+    }
+}
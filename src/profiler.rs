@@ -0,0 +1,184 @@
+//! A [`tracing`] subscriber that times spans with `performance.now()` and prints a per-tick breakdown.
+//!
+//! Requires the `"tracing"` crate feature.
+//!
+//! ```ignore
+//! yareio_sys::profiler::init().unwrap();
+//!
+//! let _tick = tracing::info_span!("tick").entered();
+//! {
+//!     let _span = tracing::info_span!("targeting").entered();
+//!     // ...
+//! }
+//! ```
+//!
+//! At the first span entered in a new tick, the previous tick's summary is printed with `console.log`:
+//!
+//! ```text
+//! tick 42: 3.81 ms
+//!   tick: 3.81 ms
+//!     pathing: 1.20 ms (24 calls)
+//!     targeting: 2.43 ms
+//! ```
+
+use crate::{console, current_tick, now};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::Mutex,
+};
+use tracing::{
+    span::{Attributes, Id, Record},
+    subscriber::SetGlobalDefaultError,
+    Event, Metadata, Subscriber,
+};
+use wasm_bindgen::JsValue;
+
+/// Installs a [`Profiler`] as the global default subscriber.
+pub fn init() -> Result<(), SetGlobalDefaultError> {
+    tracing::subscriber::set_global_default(Profiler::new())
+}
+
+/// The subscriber. Only spans are recorded; events are ignored.
+///
+/// Time is attributed to the stack of spans entered when it was spent, so the same span
+/// entered from two different parents shows up twice in the breakdown.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    /// Names and reference counts of open spans.
+    spans: HashMap<u64, (&'static str, usize)>,
+    /// Currently entered spans, with the time they were entered.
+    stack: Vec<(u64, f64)>,
+    /// The tick being recorded.
+    tick: Option<u32>,
+    /// Total time and number of calls, keyed by the names of the entered spans from the root.
+    totals: BTreeMap<Vec<&'static str>, (f64, u32)>,
+}
+
+impl Profiler {
+    #[inline]
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    /// Prints the breakdown recorded so far, and starts over.
+    pub fn report(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(summary) = state.take_summary() {
+            console::log(Box::from([JsValue::from(summary)]));
+        }
+    }
+}
+
+impl State {
+    fn take_summary(&mut self) -> Option<String> {
+        let recorded = self.tick?;
+        let totals = std::mem::take(&mut self.totals);
+        let total: f64 = totals
+            .iter()
+            .filter(|(path, _)| path.len() == 1)
+            .map(|(_, (time, _))| time)
+            .sum();
+        let mut summary = format!("tick {}: {:.2} ms", recorded, total);
+        for (path, (time, calls)) in &totals {
+            let _ = write!(
+                summary,
+                "\n{:indent$}{}: {:.2} ms",
+                "",
+                path.last().unwrap(),
+                time,
+                indent = 2 * path.len()
+            );
+            if *calls > 1 {
+                let _ = write!(summary, " ({} calls)", calls);
+            }
+        }
+        Some(summary)
+    }
+}
+
+impl Subscriber for Profiler {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span()
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.spans.insert(id, (attrs.metadata().name(), 1));
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        let mut state = self.state.lock().unwrap();
+        if state.stack.is_empty() {
+            let current = current_tick();
+            if state.tick != Some(current) {
+                if let Some(summary) = state.take_summary() {
+                    console::log(Box::from([JsValue::from(summary)]));
+                }
+                state.tick = Some(current);
+            }
+        }
+        state.stack.push((span.into_u64(), now()));
+    }
+
+    fn exit(&self, span: &Id) {
+        let end = now();
+        let mut state = self.state.lock().unwrap();
+        // Spans are normally exited in the reverse order they were entered in.
+        let index = match state
+            .stack
+            .iter()
+            .rposition(|&(id, _)| id == span.into_u64())
+        {
+            Some(index) => index,
+            None => return,
+        };
+        let path: Vec<&'static str> = state.stack[..=index]
+            .iter()
+            .map(|(id, _)| state.spans.get(id).map_or("?", |&(name, _)| name))
+            .collect();
+        let (_, start) = state.stack.remove(index);
+        let entry = state.totals.entry(path).or_insert((0.0, 0));
+        entry.0 += end - start;
+        entry.1 += 1;
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some((_, refs)) = self.state.lock().unwrap().spans.get_mut(&span.into_u64()) {
+            *refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let id = span.into_u64();
+        match state.spans.get_mut(&id) {
+            Some((_, refs)) if *refs > 1 => {
+                *refs -= 1;
+                false
+            }
+            Some(_) => {
+                state.spans.remove(&id);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -1,35 +1,76 @@
 //! Provides bindings for `RenderService` from [`yare-code-sync`](https://github.com/arikwex/yare-code-sync).
 //! To use this module, you will need to enable the crate's `RenderService` feature.
 
+use crate::color::ToCss;
 use crate::Position;
 use wasm_bindgen::prelude::*;
 
+// Positions are passed as scalars and packed into `[x, y]` arrays on the JS side, like the crate's own
+// bindings do (see the `shim` module).
+#[wasm_bindgen(inline_js = r#"
+import * as render from "yare-code-sync/client/RenderService";
+export function circle_xy(x, y, radius, color) { render.circle([x, y], radius, color); }
+export function ping_xy(x, y) { render.ping([x, y]); }
+export function line_xy(x1, y1, x2, y2, color) { render.line([x1, y1], [x2, y2], color); }
+export function text_xy(x, y, str, color) { render.text([x, y], str, color); }
+"#)]
+extern "C" {
+    fn circle_xy(x: f64, y: f64, radius: f64, color: &str);
+
+    fn ping_xy(x: f64, y: f64);
+
+    fn line_xy(x1: f64, y1: f64, x2: f64, y2: f64, color: Option<&str>);
+
+    fn text_xy(x: f64, y: f64, str: &str, color: Option<&str>);
+}
+
 #[wasm_bindgen(module = "yare-code-sync/client/RenderService")]
 extern "C" {
     #[wasm_bindgen]
-    pub fn circle(pos: Position, radius: f64, color: &str);
+    pub fn log(str: &str);
 
-    #[wasm_bindgen(js_name = "circle")]
-    pub fn circle_color(pos: Position, radius: f64, color: &str);
+    #[wasm_bindgen(js_name = "log")]
+    pub fn log_js(obj: &JsValue);
+}
 
-    #[wasm_bindgen]
-    pub fn ping(pos: Position);
+/// `circle`, with a [`Color`](crate::color::Color) or a CSS string.
+#[inline]
+pub fn circle(pos: Position, radius: f64, color: impl ToCss) {
+    circle_xy(pos.x, pos.y, radius, &color.to_css());
+}
 
-    #[wasm_bindgen]
-    pub fn line(pos1: Position, pos2: Position);
+/// Same as [`circle`].
+#[inline]
+pub fn circle_color(pos: Position, radius: f64, color: impl ToCss) {
+    circle(pos, radius, color);
+}
 
-    #[wasm_bindgen(js_name = "line")]
-    pub fn line_color(pos1: Position, pos2: Position, color: &str);
+/// `ping`
+#[inline]
+pub fn ping(pos: Position) {
+    ping_xy(pos.x, pos.y);
+}
 
-    #[wasm_bindgen]
-    pub fn text(pos: Position, str: &str);
+/// `line`
+#[inline]
+pub fn line(pos1: Position, pos2: Position) {
+    line_xy(pos1.x, pos1.y, pos2.x, pos2.y, None);
+}
 
-    #[wasm_bindgen(js_name = "text")]
-    pub fn text_color(pos: Position, str: &str, color: &str);
+/// `line`, with a [`Color`](crate::color::Color) or a CSS string.
+#[inline]
+pub fn line_color(pos1: Position, pos2: Position, color: impl ToCss) {
+    line_xy(pos1.x, pos1.y, pos2.x, pos2.y, Some(&color.to_css()));
+}
 
-    #[wasm_bindgen]
-    pub fn log(str: &str);
+/// `text`
+#[inline]
+pub fn text(pos: Position, str: &str) {
+    text_xy(pos.x, pos.y, str, None);
+}
 
-    #[wasm_bindgen(js_name = "log")]
-    pub fn log_js(obj: &JsValue);
+/// `text`, with a [`Color`](crate::color::Color) or a CSS string.
+#[inline]
+pub fn text_color(pos: Position, str: &str, color: impl ToCss) {
+    text_xy(pos.x, pos.y, str, Some(&color.to_css()));
 }
@@ -0,0 +1,205 @@
+//! The numbers behind the game rules: ranges, costs, and per-shape stats.
+//!
+//! [`Rules::V1`] holds the values of the current ruleset. The crate's own helpers, like the `try_*` methods of
+//! [`OperableSpirit`](crate::spirit::OperableSpirit), the [`spatial`](crate::spatial) queries and the simulator,
+//! read them through [`rules`], so if the game changes before this crate does, [`set_rules`] updates them all:
+//!
+//! ```ignore
+//! set_rules(Rules {
+//!     energize_range: 220.0,
+//!     ..Rules::V1
+//! });
+//! ```
+
+use crate::Shape;
+use std::cell::Cell;
+
+/// The stats of a spirit shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeRules {
+    /// The size of a newly spawned spirit. It gives that much energy per energize.
+    pub size: u32,
+    /// The energy capacity of a newly spawned spirit.
+    pub energy_capacity: i32,
+    /// How many spirits a player starts with.
+    pub starting_spirits: u32,
+    /// The cost of a new spirit, by tier. A player enters the next tier once they have as many living spirits as
+    /// the corresponding entry of [`cost_thresholds`](ShapeRules::cost_thresholds).
+    pub spirit_costs: [i32; 4],
+    pub cost_thresholds: [usize; 3],
+}
+
+impl ShapeRules {
+    /// The cost of a new spirit, given how many living spirits the player already has.
+    pub fn spirit_cost(&self, count: usize) -> i32 {
+        let tier = self
+            .cost_thresholds
+            .iter()
+            .filter(|&&threshold| count >= threshold)
+            .count();
+        self.spirit_costs[tier]
+    }
+}
+
+/// A ruleset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    /// Which ruleset these values are from. Increases when the game changes them.
+    pub version: u32,
+
+    /// How far a spirit can energize, or harvest from a star.
+    pub energize_range: f64,
+    /// How far spirits and bases see.
+    pub sight_range: f64,
+    /// How far a spirit moves per tick.
+    pub move_speed: f64,
+    /// How close circles must be to merge.
+    pub merge_range: f64,
+    /// The largest size a merged circle can have.
+    pub max_merged_size: u32,
+    /// How far a square jumps per energy spent.
+    pub jump_distance_per_energy: f64,
+    /// The radius of a triangle's explosion.
+    pub explode_radius: f64,
+    /// The energy taken from each enemy spirit in the explosion.
+    pub explode_damage: i32,
+
+    pub base_hp: i32,
+    pub base_energy_capacity: i32,
+    pub base_collision_radius: f64,
+
+    pub outpost_energy_capacity: i32,
+    pub outpost_collision_radius: f64,
+    /// How far the outpost shoots.
+    pub outpost_range: f64,
+    /// How far the outpost shoots when it has at least [`outpost_empowered_energy`](Rules::outpost_empowered_energy).
+    pub outpost_empowered_range: f64,
+    pub outpost_empowered_energy: i32,
+    /// The energy taken from the spirit the outpost shoots.
+    pub outpost_damage: i32,
+    /// The energy taken from the spirit the outpost shoots, when empowered.
+    pub outpost_empowered_damage: i32,
+
+    pub star_collision_radius: f64,
+    /// Stars regenerate `star_regen_flat + energy * star_regen_rate` energy per tick, rounded.
+    pub star_regen_flat: f64,
+    pub star_regen_rate: f64,
+
+    pub circles: ShapeRules,
+    pub squares: ShapeRules,
+    pub triangles: ShapeRules,
+}
+
+impl Rules {
+    /// The first ruleset, and the current one.
+    pub const V1: Rules = Rules {
+        version: 1,
+        energize_range: 200.0,
+        sight_range: 400.0,
+        move_speed: 20.0,
+        merge_range: 10.0,
+        max_merged_size: 100,
+        jump_distance_per_energy: 5.0,
+        explode_radius: 160.0,
+        explode_damage: 10,
+        base_hp: 5,
+        base_energy_capacity: 1000,
+        base_collision_radius: 40.0,
+        outpost_energy_capacity: 1000,
+        outpost_collision_radius: 20.0,
+        outpost_range: 400.0,
+        outpost_empowered_range: 600.0,
+        outpost_empowered_energy: 500,
+        outpost_damage: 2,
+        outpost_empowered_damage: 8,
+        star_collision_radius: 100.0,
+        star_regen_flat: 3.0,
+        star_regen_rate: 0.01,
+        circles: ShapeRules {
+            size: 1,
+            energy_capacity: 10,
+            starting_spirits: 12,
+            spirit_costs: [25, 50, 90, 150],
+            cost_thresholds: [50, 100, 200],
+        },
+        squares: ShapeRules {
+            size: 10,
+            energy_capacity: 100,
+            starting_spirits: 4,
+            spirit_costs: [360, 500, 700, 1000],
+            cost_thresholds: [10, 17, 25],
+        },
+        triangles: ShapeRules {
+            size: 3,
+            energy_capacity: 30,
+            starting_spirits: 6,
+            spirit_costs: [90, 160, 300, 500],
+            cost_thresholds: [30, 60, 120],
+        },
+    };
+
+    /// The stats of `shape`.
+    #[inline]
+    pub fn shape(&self, shape: Shape) -> &ShapeRules {
+        match shape {
+            Shape::Squares => &self.squares,
+            Shape::Triangles => &self.triangles,
+            _ => &self.circles,
+        }
+    }
+
+    /// The energy a square spends to jump `distance`.
+    #[inline]
+    pub fn jump_cost(&self, distance: f64) -> i32 {
+        (distance / self.jump_distance_per_energy).ceil() as i32
+    }
+
+    /// How far an outpost with `energy` shoots.
+    #[inline]
+    pub fn outpost_range(&self, energy: i32) -> f64 {
+        if energy >= self.outpost_empowered_energy {
+            self.outpost_empowered_range
+        } else {
+            self.outpost_range
+        }
+    }
+
+    /// How much energy an outpost with `energy` takes from the spirit it shoots.
+    #[inline]
+    pub fn outpost_damage(&self, energy: i32) -> i32 {
+        if energy >= self.outpost_empowered_energy {
+            self.outpost_empowered_damage
+        } else {
+            self.outpost_damage
+        }
+    }
+
+    /// How much energy a star with `energy` regenerates in a tick, ignoring its capacity.
+    #[inline]
+    pub fn star_regen(&self, energy: i32) -> i32 {
+        (self.star_regen_flat + f64::from(energy) * self.star_regen_rate).round() as i32
+    }
+}
+
+impl Default for Rules {
+    #[inline]
+    fn default() -> Self {
+        Rules::V1
+    }
+}
+
+thread_local! {
+    static RULES: Cell<Rules> = const { Cell::new(Rules::V1) };
+}
+
+/// The rules in effect: [`Rules::V1`], unless changed with [`set_rules`].
+#[inline]
+pub fn rules() -> Rules {
+    RULES.with(Cell::get)
+}
+
+/// Changes the rules used by the rest of the crate.
+#[inline]
+pub fn set_rules(rules: Rules) {
+    RULES.with(|r| r.set(rules));
+}
@@ -0,0 +1,143 @@
+//! Scalar bindings for the game methods that take or return positions, a fresh read of `tick`, and entity lookup
+//! by ID.
+//!
+//! The game passes positions as `[x, y]` arrays. Building or unpacking one from Rust takes an extra call to JS per
+//! coordinate, or a `Vec<f64>` allocation, so the crate's own bindings go through the small JS functions below
+//! instead: coordinates go into JS as two `f64`s, and come back out through a two-element `&mut [f64]`, which JS
+//! fills in place.
+//!
+//! The functions live on a global object, `__yareio_sys`, installed the first time one of them is needed. Other
+//! JS snippets in the crate can use it too, after calling [`install`].
+
+use crate::{Entity, Position};
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static INSTALLED: () = {
+        js_sys::Function::new_no_args(
+            r#"
+            const write = (pos, out) => {
+                out[0] = pos[0];
+                out[1] = pos[1];
+            };
+            globalThis.__yareio_sys = {
+                tick: () => tick,
+                entity: (id) => spirits[id] || bases[id] || outposts[id] || stars[id],
+                position_of: (x, y) => [x, y],
+                write_position: write,
+                write_entity_position: (entity, out) => write(entity.position, out),
+                move_to: (spirit, x, y) => spirit.move([x, y]),
+                jump_to: (spirit, x, y) => spirit.jump([x, y]),
+                line: (x1, y1, x2, y2) => graphics.line([x1, y1], [x2, y2]),
+                circle: (x, y, r) => graphics.circle([x, y], r),
+                rect: (x1, y1, x2, y2) => graphics.rect([x1, y1], [x2, y2]),
+            };
+            "#,
+        )
+        .call0(&JsValue::NULL)
+        .unwrap_throw();
+    };
+}
+
+#[wasm_bindgen(js_namespace = __yareio_sys)]
+extern "C" {
+    #[wasm_bindgen(js_name = "tick")]
+    fn tick_js() -> u32;
+
+    #[wasm_bindgen(js_name = "entity")]
+    fn entity_js(id: &str) -> Option<Entity>;
+
+    fn position_of(x: f64, y: f64) -> JsValue;
+
+    fn write_position(pos: &JsValue, out: &mut [f64]);
+
+    fn write_entity_position(entity: &Entity, out: &mut [f64]);
+
+    fn move_to(spirit: &JsValue, x: f64, y: f64);
+
+    fn jump_to(spirit: &JsValue, x: f64, y: f64);
+
+    #[wasm_bindgen(js_name = "line")]
+    fn line_xy(x1: f64, y1: f64, x2: f64, y2: f64);
+
+    #[wasm_bindgen(js_name = "circle")]
+    fn circle_xy(x: f64, y: f64, r: f64);
+
+    #[wasm_bindgen(js_name = "rect")]
+    fn rect_xy(x1: f64, y1: f64, x2: f64, y2: f64);
+}
+
+#[inline]
+pub(crate) fn install() {
+    INSTALLED.with(|_| ());
+}
+
+#[inline]
+pub(crate) fn tick() -> u32 {
+    install();
+    tick_js()
+}
+
+/// The spirit, base, outpost or star with ID `id`.
+#[inline]
+pub(crate) fn entity(id: &str) -> Option<Entity> {
+    install();
+    entity_js(id)
+}
+
+/// Calls `write` with two `f64`s for JS to fill in.
+#[inline]
+fn read(write: impl FnOnce(&mut [f64])) -> Position {
+    install();
+    let mut out = [0.0; 2];
+    write(&mut out);
+    Position::from(out)
+}
+
+/// A new `[x, y]` array.
+#[inline]
+pub(crate) fn to_js(pos: Position) -> JsValue {
+    install();
+    position_of(pos.x, pos.y)
+}
+
+/// The position in an `[x, y]` array.
+#[inline]
+pub(crate) fn from_js(pos: &JsValue) -> Position {
+    read(|out| write_position(pos, out))
+}
+
+#[inline]
+pub(crate) fn entity_position(entity: &Entity) -> Position {
+    read(|out| write_entity_position(entity, out))
+}
+
+#[inline]
+pub(crate) fn r#move(spirit: &JsValue, target: Position) {
+    install();
+    move_to(spirit, target.x, target.y);
+}
+
+#[inline]
+pub(crate) fn jump(spirit: &JsValue, target: Position) {
+    install();
+    jump_to(spirit, target.x, target.y);
+}
+
+#[inline]
+pub(crate) fn line(pos: Position, end: Position) {
+    install();
+    line_xy(pos.x, pos.y, end.x, end.y);
+}
+
+#[inline]
+pub(crate) fn circle(pos: Position, r: f64) {
+    install();
+    circle_xy(pos.x, pos.y, r);
+}
+
+#[inline]
+pub(crate) fn rect(tl: Position, br: Position) {
+    install();
+    rect_xy(tl.x, tl.y, br.x, br.y);
+}
@@ -0,0 +1,849 @@
+//! A native implementation of the game rules, for testing bots outside of Yare.
+//! To use this module, you will need to enable the crate's `sim` feature.
+//!
+//! The game state is a [`Snapshot`]; a [`Simulation`] steps it forward one tick at a time,
+//! applying the [`Command`]s issued to each spirit.
+//! Nothing here calls into JS, so it works with `cargo test` on any platform.
+//!
+//! The rules are an approximation of Yare's, good enough to exercise bot logic;
+//! don't expect replays to match the real game tick for tick.
+
+use crate::commands::{Command, CommandSlot};
+use crate::game::{Entities, Game, GameView};
+use crate::rules::{rules, Rules};
+use crate::snapshot::{
+    BaseSnapshot, OutpostSightSnapshot, OutpostSnapshot, PlayersSnapshot, SightSnapshot, Snapshot,
+    SpiritSnapshot, StarSnapshot,
+};
+use crate::{Position, Shape};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// A match being simulated.
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    state: Snapshot,
+    rules: Rules,
+    orders: BTreeMap<String, BTreeMap<CommandSlot, Command>>,
+    spawned: BTreeMap<String, u32>,
+}
+
+impl Simulation {
+    /// Starts a simulation from an arbitrary state, seen from the perspective of `state.this_player_id`.
+    /// It plays by the current [`rules`].
+    pub fn new(mut state: Snapshot) -> Self {
+        state.classify();
+        let mut spawned = BTreeMap::new();
+        for spirit in state.spirits.values() {
+            *spawned.entry(spirit.player_id.clone()).or_insert(0) += 1;
+        }
+        let mut sim = Simulation {
+            state,
+            rules: rules(),
+            orders: BTreeMap::new(),
+            spawned,
+        };
+        sim.update_derived();
+        sim
+    }
+
+    /// Starts a match on the standard map, between players `"p1"` (you) and `"p2"`.
+    pub fn standard(p1_shape: Shape, p2_shape: Shape) -> Self {
+        let rules = rules();
+        let mut state = Snapshot {
+            tick: 1,
+            this_player_id: "p1".to_owned(),
+            players: PlayersSnapshot {
+                p1: "p1".to_owned(),
+                p2: "p2".to_owned(),
+            },
+            ..Snapshot::default()
+        };
+
+        let players = [
+            ("p1", p1_shape, "base_zxq", Position::new(1600.0, 700.0)),
+            ("p2", p2_shape, "base_a1c", Position::new(2600.0, 1700.0)),
+        ];
+        for &(player_id, shape, base_id, position) in players.iter() {
+            state.bases.insert(
+                base_id.to_owned(),
+                BaseSnapshot {
+                    id: base_id.to_owned(),
+                    position,
+                    size: 40,
+                    energy: 0,
+                    energy_capacity: rules.base_energy_capacity,
+                    last_energized: None,
+                    hp: rules.base_hp,
+                    sight: SightSnapshot::default(),
+                    player_id: player_id.to_owned(),
+                    shape,
+                    color: String::new(),
+                    current_spirit_cost: rules.shape(shape).spirit_cost(0),
+                    collision_radius: rules.base_collision_radius,
+                    friendly: false,
+                },
+            );
+        }
+
+        let stars = [
+            ("star_zxq", Position::new(1000.0, 1000.0), 1000, 0),
+            ("star_a1c", Position::new(3200.0, 1400.0), 1000, 0),
+            ("star_p89", Position::new(2000.0, 1300.0), 3000, 100),
+        ];
+        for &(star_id, position, energy_capacity, active_at) in stars.iter() {
+            state.stars.insert(
+                star_id.to_owned(),
+                StarSnapshot {
+                    id: star_id.to_owned(),
+                    position,
+                    size: 220,
+                    energy: if active_at == 0 { 100 } else { 0 },
+                    energy_capacity,
+                    last_energized: None,
+                    active_in: active_at,
+                    active_at,
+                    collision_radius: rules.star_collision_radius,
+                },
+            );
+        }
+
+        state.outposts.insert(
+            "outpost_mdo".to_owned(),
+            OutpostSnapshot {
+                id: "outpost_mdo".to_owned(),
+                position: Position::new(2200.0, 1100.0),
+                size: 20,
+                energy: 0,
+                energy_capacity: rules.outpost_energy_capacity,
+                last_energized: None,
+                range: rules.outpost_range,
+                sight: OutpostSightSnapshot::default(),
+                control: String::new(),
+                collision_radius: rules.outpost_collision_radius,
+            },
+        );
+
+        let mut sim = Simulation::new(state);
+        for &(player_id, shape, base_id, _) in players.iter() {
+            for _ in 0..rules.shape(shape).starting_spirits {
+                sim.spawn(player_id, shape, base_id, 10);
+            }
+        }
+        sim.update_derived();
+        sim
+    }
+
+    /// The rules the simulation plays by.
+    #[inline]
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Changes the rules the simulation plays by, from the next [`step`](Simulation::step) on.
+    #[inline]
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    /// The current state, seen by [`this_player_id`](Snapshot::this_player_id).
+    #[inline]
+    pub fn state(&self) -> &Snapshot {
+        &self.state
+    }
+
+    /// Mutable access to the current state, to set up scenarios.
+    /// Call [`Snapshot::classify`] afterwards if you change any player IDs.
+    #[inline]
+    pub fn state_mut(&mut self) -> &mut Snapshot {
+        &mut self.state
+    }
+
+    /// The current state, seen by `player_id`.
+    pub fn view_as(&self, player_id: &str) -> Snapshot {
+        let mut state = self.state.clone();
+        state.this_player_id = player_id.to_owned();
+        state.classify();
+        state
+    }
+
+    /// Switches [`this_player_id`](Snapshot::this_player_id), so that the [`Game`] implementation
+    /// controls `player_id`'s spirits. Use this to have two bots play against each other.
+    pub fn play_as(&mut self, player_id: &str) {
+        self.state.this_player_id = player_id.to_owned();
+        self.state.classify();
+    }
+
+    /// The player whose base is still standing, if the other one has been destroyed.
+    pub fn winner(&self) -> Option<&str> {
+        let mut standing = self.state.bases.values().filter(|base| base.is_living());
+        match (standing.next(), standing.next()) {
+            (Some(base), None) => Some(&base.player_id),
+            _ => None,
+        }
+    }
+
+    /// Gives a command to a spirit, to be carried out on the next [`step`](Simulation::step).
+    ///
+    /// Like in the game, a later command replaces an earlier one in the same [slot](CommandSlot),
+    /// and commands given to spirits that can't carry them out are silently ignored.
+    pub fn issue(&mut self, spirit_id: &str, command: Command) {
+        self.orders
+            .entry(spirit_id.to_owned())
+            .or_default()
+            .insert(command.slot(), command);
+    }
+
+    /// The commands issued since the last [`step`](Simulation::step).
+    pub fn pending(&self) -> impl Iterator<Item = (&str, &Command)> {
+        self.orders.iter().flat_map(|(spirit_id, orders)| {
+            orders
+                .values()
+                .map(move |command| (spirit_id.as_str(), command))
+        })
+    }
+
+    /// Advances the game by one tick.
+    pub fn step(&mut self) {
+        let orders = std::mem::take(&mut self.orders);
+        let orders: Vec<(String, Command)> = orders
+            .into_iter()
+            .filter(|(spirit_id, _)| {
+                matches!(self.state.spirits.get(spirit_id), Some(spirit) if spirit.is_living())
+            })
+            .flat_map(|(spirit_id, orders)| {
+                orders
+                    .into_values()
+                    .map(move |command| (spirit_id.clone(), command))
+            })
+            .collect();
+
+        for (spirit_id, command) in &orders {
+            match command {
+                Command::Move(target) => self.move_spirit(spirit_id, *target),
+                Command::Jump(target) => self.jump(spirit_id, *target),
+                // Shouts are only displayed, they don't affect the game.
+                Command::Shout(_) => {}
+                Command::SetMark(mark) => {
+                    if let Some(spirit) = self.state.spirits.get_mut(spirit_id) {
+                        spirit.mark = mark.clone();
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (spirit_id, command) in &orders {
+            match command {
+                Command::Merge(target_id) => self.merge(spirit_id, target_id),
+                Command::Divide => self.divide(spirit_id),
+                _ => {}
+            }
+        }
+        let energize: Vec<(&String, &String)> = orders
+            .iter()
+            .filter_map(|(spirit_id, command)| match command {
+                Command::Energize(target_id) => Some((spirit_id, target_id)),
+                _ => None,
+            })
+            .collect();
+        self.energize(&energize);
+        for (spirit_id, command) in &orders {
+            if let Command::Explode = command {
+                self.explode(spirit_id);
+            }
+        }
+        self.outposts_fire();
+        self.kill_spirits();
+        self.update_bases();
+        self.regenerate_stars();
+
+        self.state.tick += 1;
+        self.update_derived();
+    }
+
+    /// Steps until a base is destroyed or `max_ticks` have passed, calling `bot` before each tick.
+    /// Returns the winner, if any.
+    pub fn run(&mut self, max_ticks: u32, mut bot: impl FnMut(&mut Simulation)) -> Option<String> {
+        for _ in 0..max_ticks {
+            if let Some(winner) = self.winner() {
+                return Some(winner.to_owned());
+            }
+            bot(self);
+            self.step();
+        }
+        self.winner().map(str::to_owned)
+    }
+
+    fn spawn(&mut self, player_id: &str, shape: Shape, base_id: &str, energy: i32) {
+        let count = self.spawned.entry(player_id.to_owned()).or_insert(0);
+        *count += 1;
+        let id = format!("{}_{}", player_id, count);
+        let base_position = self.state.bases[base_id].position;
+        // Spread spirits around the base on a golden-angle spiral.
+        let offset = Position::from_angle(f64::from(*count) * 2.399_963)
+            * (60.0 + 2.0 * f64::from(*count % 20));
+        let stats = self.rules.shape(shape);
+        let (size, energy_capacity) = (stats.size, stats.energy_capacity);
+        let spirit = SpiritSnapshot {
+            id: id.clone(),
+            position: base_position + offset,
+            size,
+            energy: energy.min(energy_capacity),
+            energy_capacity,
+            last_energized: None,
+            hp: 1,
+            sight: SightSnapshot::default(),
+            player_id: player_id.to_owned(),
+            shape,
+            color: String::new(),
+            merged: Vec::new(),
+            move_speed: self.rules.move_speed,
+            mark: String::new(),
+            friendly: player_id == self.state.this_player_id,
+        };
+        self.state.spirits.insert(id, spirit);
+    }
+
+    fn move_spirit(&mut self, spirit_id: &str, target: Position) {
+        if let Some(spirit) = self.state.spirits.get_mut(spirit_id) {
+            spirit.position = spirit.position.move_towards(target, spirit.move_speed);
+        }
+    }
+
+    fn jump(&mut self, spirit_id: &str, target: Position) {
+        if let Some(spirit) = self.state.spirits.get_mut(spirit_id) {
+            let cost = self.rules.jump_cost(spirit.position.distance(target));
+            if spirit.shape == Shape::Squares && spirit.energy >= cost {
+                spirit.energy -= cost;
+                spirit.position = target;
+            }
+        }
+    }
+
+    fn merge(&mut self, spirit_id: &str, target_id: &str) {
+        let (source, target) = match (
+            self.state.spirits.get(spirit_id),
+            self.state.spirits.get(target_id),
+        ) {
+            (Some(source), Some(target)) => (source, target),
+            _ => return,
+        };
+        if spirit_id == target_id
+            || source.shape != Shape::Circles
+            || target.shape != Shape::Circles
+            || !source.is_living()
+            || !target.is_living()
+            || source.player_id != target.player_id
+            || source.position.distance(target.position) > self.rules.merge_range
+            || source.size + target.size > self.rules.max_merged_size
+        {
+            return;
+        }
+        let (size, energy) = (source.size, source.energy);
+        let mut merged = source.merged.clone();
+        merged.push(spirit_id.to_owned());
+
+        let source = self.state.spirits.get_mut(spirit_id).unwrap();
+        source.hp = 0;
+        source.energy = 0;
+        source.merged.clear();
+        let target = self.state.spirits.get_mut(target_id).unwrap();
+        target.size += size;
+        target.energy_capacity = self.rules.circles.energy_capacity * target.size as i32;
+        target.energy += energy;
+        target.merged.append(&mut merged);
+    }
+
+    fn divide(&mut self, spirit_id: &str) {
+        let (size, energy_capacity) = (self.rules.circles.size, self.rules.circles.energy_capacity);
+        let spirit = match self.state.spirits.get_mut(spirit_id) {
+            Some(spirit) if spirit.shape == Shape::Circles && !spirit.merged.is_empty() => spirit,
+            _ => return,
+        };
+        let merged = std::mem::take(&mut spirit.merged);
+        let parts = merged.len() as i32 + 1;
+        let share = spirit.energy / parts;
+        spirit.energy -= share * (parts - 1);
+        spirit.size = size;
+        spirit.energy_capacity = energy_capacity;
+        let position = spirit.position;
+        for id in merged {
+            if let Some(part) = self.state.spirits.get_mut(&id) {
+                part.hp = 1;
+                part.size = size;
+                part.energy_capacity = energy_capacity;
+                part.energy = share;
+                part.position = position;
+            }
+        }
+    }
+
+    fn energize(&mut self, orders: &[(&String, &String)]) {
+        let energize_range = self.rules.energize_range;
+        // Energy transfers are simultaneous: compute them all from the state at the start of the phase.
+        let mut deltas: BTreeMap<&str, i32> = BTreeMap::new();
+        let mut star_deltas: BTreeMap<String, i32> = BTreeMap::new();
+        let mut outposts: Vec<(&str, &str, i32)> = Vec::new();
+        let mut energized: Vec<(&str, &str)> = Vec::new();
+
+        for &(spirit_id, target_id) in orders {
+            let spirit = &self.state.spirits[spirit_id.as_str()];
+            if !spirit.is_living() {
+                continue;
+            }
+            let amount = (spirit.size as i32).min(spirit.energy);
+
+            if spirit_id == target_id {
+                // Harvest from a star in range.
+                let star = self.state.stars.values().find(|star| {
+                    star.position.distance(spirit.position) <= energize_range
+                        && self.state.tick >= star.active_at
+                });
+                if let Some(star) = star {
+                    let amount = (spirit.size as i32)
+                        .min(star.energy + star_deltas.get(&star.id).copied().unwrap_or(0))
+                        .min(spirit.energy_capacity - spirit.energy)
+                        .max(0);
+                    *star_deltas.entry(star.id.clone()).or_insert(0) -= amount;
+                    *deltas.entry(spirit_id).or_insert(0) += amount;
+                }
+                continue;
+            }
+
+            let in_range = matches!(
+                self.state.position_of(target_id),
+                Some(position) if position.distance(spirit.position) <= energize_range
+            );
+            if !in_range || amount <= 0 {
+                continue;
+            }
+            if let Some(target) = self.state.spirits.get(target_id.as_str()) {
+                if !target.is_living() {
+                    continue;
+                }
+                *deltas.entry(spirit_id).or_insert(0) -= amount;
+                if target.player_id == spirit.player_id {
+                    *deltas.entry(target_id).or_insert(0) += amount;
+                } else {
+                    *deltas.entry(target_id).or_insert(0) -= 2 * amount;
+                }
+            } else if let Some(base) = self.state.bases.get(target_id.as_str()) {
+                *deltas.entry(spirit_id).or_insert(0) -= amount;
+                if base.player_id == spirit.player_id {
+                    *deltas.entry(target_id).or_insert(0) += amount;
+                } else {
+                    *deltas.entry(target_id).or_insert(0) -= 2 * amount;
+                }
+            } else if self.state.outposts.contains_key(target_id.as_str()) {
+                *deltas.entry(spirit_id).or_insert(0) -= amount;
+                outposts.push((target_id, &spirit.player_id, amount));
+            } else {
+                continue;
+            }
+            energized.push((target_id, spirit_id));
+        }
+
+        let deltas: Vec<(String, i32)> = deltas
+            .into_iter()
+            .map(|(id, delta)| (id.to_owned(), delta))
+            .collect();
+        let outposts: Vec<(String, String, i32)> = outposts
+            .into_iter()
+            .map(|(id, player_id, amount)| (id.to_owned(), player_id.to_owned(), amount))
+            .collect();
+        let energized: Vec<(String, String)> = energized
+            .into_iter()
+            .map(|(target_id, spirit_id)| (target_id.to_owned(), spirit_id.to_owned()))
+            .collect();
+
+        for (id, delta) in deltas {
+            if let Some(spirit) = self.state.spirits.get_mut(&id) {
+                spirit.energy = (spirit.energy + delta).min(spirit.energy_capacity);
+            } else if let Some(base) = self.state.bases.get_mut(&id) {
+                base.energy = (base.energy + delta).min(base.energy_capacity);
+            }
+        }
+        for (id, delta) in star_deltas {
+            let star = self.state.stars.get_mut(&id).unwrap();
+            star.energy += delta;
+        }
+        for (id, player_id, amount) in outposts {
+            let outpost = self.state.outposts.get_mut(&id).unwrap();
+            if outpost.control.is_empty() || outpost.control == player_id {
+                outpost.control = player_id;
+                outpost.energy = (outpost.energy + amount).min(outpost.energy_capacity);
+            } else {
+                outpost.energy -= 2 * amount;
+                if outpost.energy <= 0 {
+                    outpost.energy = 0;
+                    outpost.control = player_id;
+                }
+            }
+        }
+        for (target_id, spirit_id) in energized {
+            let last_energized = Some(spirit_id);
+            if let Some(spirit) = self.state.spirits.get_mut(&target_id) {
+                spirit.last_energized = last_energized;
+            } else if let Some(base) = self.state.bases.get_mut(&target_id) {
+                base.last_energized = last_energized;
+            } else if let Some(outpost) = self.state.outposts.get_mut(&target_id) {
+                outpost.last_energized = last_energized;
+            }
+        }
+    }
+
+    fn explode(&mut self, spirit_id: &str) {
+        let spirit = &self.state.spirits[spirit_id];
+        if spirit.shape != Shape::Triangles || !spirit.is_living() {
+            return;
+        }
+        let (position, player_id) = (spirit.position, spirit.player_id.clone());
+        for target in self.state.spirits.values_mut() {
+            if target.player_id != player_id
+                && target.is_living()
+                && target.position.distance(position) <= self.rules.explode_radius
+            {
+                target.energy -= self.rules.explode_damage;
+            }
+        }
+        let spirit = self.state.spirits.get_mut(spirit_id).unwrap();
+        spirit.hp = 0;
+        spirit.energy = 0;
+    }
+
+    fn outposts_fire(&mut self) {
+        for outpost in self.state.outposts.values_mut() {
+            if outpost.control.is_empty() || outpost.energy <= 0 {
+                continue;
+            }
+            let target = self
+                .state
+                .spirits
+                .values_mut()
+                .filter(|spirit| {
+                    spirit.player_id != outpost.control
+                        && spirit.is_living()
+                        && spirit.position.distance(outpost.position) <= outpost.range
+                })
+                .min_by(|a, b| {
+                    let a = a.position.distance_squared(outpost.position);
+                    let b = b.position.distance_squared(outpost.position);
+                    a.total_cmp(&b)
+                });
+            if let Some(target) = target {
+                target.energy -= self.rules.outpost_damage(outpost.energy);
+                outpost.energy -= 1;
+            }
+        }
+    }
+
+    fn kill_spirits(&mut self) {
+        for spirit in self.state.spirits.values_mut() {
+            if spirit.energy < 0 {
+                spirit.hp = 0;
+                spirit.energy = 0;
+            }
+        }
+    }
+
+    fn update_bases(&mut self) {
+        let mut spawns = Vec::new();
+        for base in self.state.bases.values_mut() {
+            if base.energy < 0 {
+                base.hp = (base.hp - 1).max(0);
+                base.energy = 0;
+            }
+            if base.is_living() && base.energy >= base.current_spirit_cost {
+                base.energy -= base.current_spirit_cost;
+                spawns.push((base.player_id.clone(), base.shape, base.id.clone()));
+            }
+        }
+        for (player_id, shape, base_id) in spawns {
+            self.spawn(&player_id, shape, &base_id, 0);
+        }
+    }
+
+    fn regenerate_stars(&mut self) {
+        let tick = self.state.tick;
+        for star in self.state.stars.values_mut() {
+            if tick >= star.active_at {
+                star.energy =
+                    (star.energy + self.rules.star_regen(star.energy)).min(star.energy_capacity);
+            }
+        }
+    }
+
+    /// Recomputes everything that follows from the rest of the state: sights, outpost ranges,
+    /// spirit costs, and star activation countdowns.
+    fn update_derived(&mut self) {
+        let rules = self.rules;
+        let tick = self.state.tick;
+        for star in self.state.stars.values_mut() {
+            star.active_in = star.active_at.saturating_sub(tick);
+        }
+        for outpost in self.state.outposts.values_mut() {
+            outpost.range = rules.outpost_range(outpost.energy);
+        }
+
+        let living: Vec<(String, String, Position)> = self
+            .state
+            .spirits
+            .values()
+            .filter(|spirit| spirit.is_living())
+            .map(|spirit| (spirit.id.clone(), spirit.player_id.clone(), spirit.position))
+            .collect();
+        let structures: Vec<(String, Position)> = self
+            .state
+            .bases
+            .values()
+            .map(|base| (base.id.clone(), base.position))
+            .chain(
+                self.state
+                    .outposts
+                    .values()
+                    .map(|outpost| (outpost.id.clone(), outpost.position)),
+            )
+            .chain(
+                self.state
+                    .stars
+                    .values()
+                    .map(|star| (star.id.clone(), star.position)),
+            )
+            .collect();
+        let sight = |id: &str, player_id: &str, position: Position| {
+            let mut sight = SightSnapshot::default();
+            for (other_id, other_player_id, other_position) in &living {
+                let distance = other_position.distance(position);
+                if other_id == id || distance > rules.sight_range {
+                    continue;
+                }
+                let (all, beamable) = if other_player_id == player_id {
+                    (&mut sight.friends, &mut sight.friends_beamable)
+                } else {
+                    (&mut sight.enemies, &mut sight.enemies_beamable)
+                };
+                all.push(other_id.clone());
+                if distance <= rules.energize_range {
+                    beamable.push(other_id.clone());
+                }
+            }
+            for (structure_id, structure_position) in &structures {
+                if structure_id != id && structure_position.distance(position) <= rules.sight_range
+                {
+                    sight.structures.push(structure_id.clone());
+                }
+            }
+            sight
+        };
+
+        for spirit in self.state.spirits.values_mut() {
+            spirit.sight = if spirit.is_living() {
+                sight(&spirit.id, &spirit.player_id, spirit.position)
+            } else {
+                SightSnapshot::default()
+            };
+        }
+        for base in self.state.bases.values_mut() {
+            base.sight = sight(&base.id, &base.player_id, base.position);
+            let count = living
+                .iter()
+                .filter(|(_, player_id, _)| player_id == &base.player_id)
+                .count();
+            base.current_spirit_cost = rules.shape(base.shape).spirit_cost(count);
+        }
+        for outpost in self.state.outposts.values_mut() {
+            outpost.sight.enemies = if outpost.control.is_empty() {
+                Vec::new()
+            } else {
+                living
+                    .iter()
+                    .filter(|(_, player_id, position)| {
+                        player_id != &outpost.control
+                            && position.distance(outpost.position) <= outpost.range
+                    })
+                    .map(|(id, _, _)| id.clone())
+                    .collect()
+            };
+        }
+    }
+}
+
+impl GameView for Simulation {
+    #[inline]
+    fn tick(&self) -> u32 {
+        self.state.tick()
+    }
+
+    #[inline]
+    fn this_player_id(&self) -> Cow<'_, str> {
+        self.state.this_player_id()
+    }
+
+    fn spirits(&self) -> Entities<'_, SpiritSnapshot> {
+        self.state.spirits()
+    }
+
+    fn spirit_by_id(&self, id: &str) -> Option<Cow<'_, SpiritSnapshot>> {
+        self.state.spirit_by_id(id)
+    }
+
+    fn bases(&self) -> Entities<'_, BaseSnapshot> {
+        self.state.bases()
+    }
+
+    fn base_by_id(&self, id: &str) -> Option<Cow<'_, BaseSnapshot>> {
+        self.state.base_by_id(id)
+    }
+
+    fn outposts(&self) -> Entities<'_, OutpostSnapshot> {
+        self.state.outposts()
+    }
+
+    fn outpost_by_id(&self, id: &str) -> Option<Cow<'_, OutpostSnapshot>> {
+        self.state.outpost_by_id(id)
+    }
+
+    fn stars(&self) -> Entities<'_, StarSnapshot> {
+        self.state.stars()
+    }
+
+    fn star_by_id(&self, id: &str) -> Option<Cow<'_, StarSnapshot>> {
+        self.state.star_by_id(id)
+    }
+}
+
+impl Game for Simulation {
+    /// [Issues](Simulation::issue) the command, if the spirit belongs to [`this_player_id`](Snapshot::this_player_id).
+    fn command(&mut self, spirit_id: &str, command: Command) {
+        if matches!(self.state.spirits.get(spirit_id), Some(spirit) if spirit.friendly) {
+            self.issue(spirit_id, command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A standard match between circles, with spirit `id` moved to `position`.
+    fn with_spirit_at(id: &str, position: Position) -> Simulation {
+        let mut sim = Simulation::standard(Shape::Circles, Shape::Circles);
+        sim.state_mut().spirits.get_mut(id).unwrap().position = position;
+        sim
+    }
+
+    fn spirit<'a>(sim: &'a Simulation, id: &str) -> &'a SpiritSnapshot {
+        &sim.state().spirits[id]
+    }
+
+    #[test]
+    fn move_goes_at_most_move_speed() {
+        let mut sim = with_spirit_at("p1_1", Position::new(0.0, 0.0));
+        sim.issue("p1_1", Command::Move(Position::new(100.0, 0.0)));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").position, Position::new(20.0, 0.0));
+
+        sim.issue("p1_1", Command::Move(Position::new(30.0, 0.0)));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").position, Position::new(30.0, 0.0));
+    }
+
+    #[test]
+    fn harvest_needs_an_active_star_within_range_of_its_center() {
+        let star = Simulation::standard(Shape::Circles, Shape::Circles)
+            .state()
+            .stars["star_zxq"]
+            .position;
+        let mut sim = with_spirit_at("p1_1", star + Position::new(200.0, 0.0));
+        sim.state_mut().spirits.get_mut("p1_1").unwrap().energy = 0;
+        sim.issue("p1_1", Command::Energize("p1_1".to_owned()));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").energy, 1);
+        let regen = sim.rules().star_regen(99);
+        assert_eq!(sim.state().stars["star_zxq"].energy, 99 + regen);
+
+        let mut sim = with_spirit_at("p1_1", star + Position::new(201.0, 0.0));
+        sim.state_mut().spirits.get_mut("p1_1").unwrap().energy = 0;
+        sim.issue("p1_1", Command::Energize("p1_1".to_owned()));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").energy, 0);
+
+        let inactive = sim.state().stars["star_p89"].position;
+        let mut sim = with_spirit_at("p1_1", inactive);
+        sim.state_mut().spirits.get_mut("p1_1").unwrap().energy = 0;
+        sim.issue("p1_1", Command::Energize("p1_1".to_owned()));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").energy, 0);
+    }
+
+    #[test]
+    fn energize_damages_enemies_twice_as_much() {
+        let mut sim = with_spirit_at("p1_1", Position::new(0.0, 0.0));
+        sim.state_mut().spirits.get_mut("p2_1").unwrap().position = Position::new(150.0, 0.0);
+        sim.issue("p1_1", Command::Energize("p2_1".to_owned()));
+        sim.step();
+        assert_eq!(spirit(&sim, "p1_1").energy, 9);
+        assert_eq!(spirit(&sim, "p2_1").energy, 8);
+        assert_eq!(spirit(&sim, "p2_1").last_energized.as_deref(), Some("p1_1"));
+    }
+
+    #[test]
+    fn base_spawns_when_it_has_the_spirit_cost() {
+        let mut sim = Simulation::standard(Shape::Circles, Shape::Circles);
+        let cost = sim.state().bases["base_zxq"].current_spirit_cost;
+        assert_eq!(cost, sim.rules().circles.spirit_cost(12));
+        sim.state_mut().bases.get_mut("base_zxq").unwrap().energy = cost - 1;
+        sim.step();
+        assert_eq!(sim.state().spirits.len(), 24);
+
+        sim.state_mut().bases.get_mut("base_zxq").unwrap().energy = cost;
+        sim.step();
+        assert_eq!(sim.state().spirits.len(), 25);
+        assert_eq!(sim.state().bases["base_zxq"].energy, 0);
+        let spawned = spirit(&sim, "p1_13");
+        assert_eq!((spawned.energy, spawned.size), (0, 1));
+    }
+
+    #[test]
+    fn outpost_shoots_the_nearest_enemy_in_range() {
+        let mut sim = Simulation::standard(Shape::Circles, Shape::Circles);
+        let position = sim.state().outposts["outpost_mdo"].position;
+        {
+            let outpost = sim.state_mut().outposts.get_mut("outpost_mdo").unwrap();
+            outpost.control = "p1".to_owned();
+            outpost.energy = 10;
+        }
+        let spirits = &mut sim.state_mut().spirits;
+        spirits.get_mut("p2_1").unwrap().position = position + Position::new(300.0, 0.0);
+        spirits.get_mut("p2_2").unwrap().position = position + Position::new(100.0, 0.0);
+        spirits.get_mut("p1_1").unwrap().position = position + Position::new(50.0, 0.0);
+        sim.step();
+        assert_eq!(spirit(&sim, "p2_2").energy, 10 - sim.rules().outpost_damage);
+        assert_eq!(spirit(&sim, "p2_1").energy, 10);
+        assert_eq!(spirit(&sim, "p1_1").energy, 10);
+        assert_eq!(sim.state().outposts["outpost_mdo"].energy, 9);
+    }
+
+    #[test]
+    fn merge_then_divide_restores_the_circles() {
+        let mut sim = with_spirit_at("p1_1", Position::new(0.0, 0.0));
+        sim.state_mut().spirits.get_mut("p1_2").unwrap().position = Position::new(5.0, 0.0);
+        sim.issue("p1_1", Command::Merge("p1_2".to_owned()));
+        sim.step();
+        assert!(!spirit(&sim, "p1_1").is_living());
+        let merged = spirit(&sim, "p1_2");
+        assert_eq!(
+            (merged.size, merged.energy, merged.energy_capacity),
+            (2, 20, 20)
+        );
+        assert_eq!(merged.merged, vec!["p1_1".to_owned()]);
+
+        sim.issue("p1_2", Command::Divide);
+        sim.step();
+        for id in &["p1_1", "p1_2"] {
+            let part = spirit(&sim, id);
+            assert!(part.is_living());
+            assert_eq!((part.size, part.energy, part.energy_capacity), (1, 10, 10));
+        }
+    }
+}
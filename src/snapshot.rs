@@ -0,0 +1,390 @@
+//! Owned snapshots of the game state.
+//!
+//! Every getter in the rest of this crate is a separate call into JS.
+//! [`Snapshot::capture`] instead copies everything visible to your bot into plain Rust structs
+//! in a single bulk transfer, after which reading the state costs nothing.
+//! Capture one snapshot at the start of each tick, and read from it instead of the bindings.
+//!
+//! Entities are indexed by ID. IDs are kept as [`String`]s; use the corresponding `GetByID` implementation
+//! if you need the live JS object.
+//! Conversely, the snapshot structs implement `From` the corresponding JS objects, reading them property by property.
+
+use crate::base::{bases, Base};
+use crate::outpost::{outposts, Outpost};
+use crate::players::{players, this_player_id};
+use crate::spirit::{spirits, Spirit};
+use crate::star::{stars, Star};
+use crate::{current_tick, LivingDestructible, OutpostSight, Position, Shape, Sight};
+use js_sys::{Object, Reflect, JSON};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// A JS exception, as a [`serde_json::Error`].
+fn js_error(err: JsValue) -> serde_json::Error {
+    serde::ser::Error::custom(format!("{:?}", err))
+}
+
+/// An empty string stands for "no entity" in JS.
+fn empty_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.filter(|string| !string.is_empty()))
+}
+
+/// Reads an ID (or any other JS string) into a [`String`].
+#[inline]
+fn read_id(id: &JsValue) -> String {
+    id.as_string().unwrap_or_default()
+}
+
+#[inline]
+fn read_ids<T: AsRef<JsValue>>(ids: Vec<T>) -> Vec<String> {
+    ids.iter().map(|id| read_id(id.as_ref())).collect()
+}
+
+/// A snapshot of a [`Sight`](crate::Sight).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SightSnapshot {
+    pub friends: Vec<String>,
+    pub friends_beamable: Vec<String>,
+    pub enemies: Vec<String>,
+    pub enemies_beamable: Vec<String>,
+    pub structures: Vec<String>,
+}
+
+impl From<&Sight> for SightSnapshot {
+    fn from(sight: &Sight) -> Self {
+        SightSnapshot {
+            friends: read_ids(sight.friends()),
+            friends_beamable: read_ids(sight.friends_beamable()),
+            enemies: read_ids(sight.enemies()),
+            enemies_beamable: read_ids(sight.enemies_beamable()),
+            structures: read_ids(sight.structures()),
+        }
+    }
+}
+
+/// A snapshot of an [`OutpostSight`](crate::OutpostSight).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutpostSightSnapshot {
+    pub enemies: Vec<String>,
+}
+
+impl From<&OutpostSight> for OutpostSightSnapshot {
+    fn from(sight: &OutpostSight) -> Self {
+        OutpostSightSnapshot {
+            enemies: read_ids(sight.enemies()),
+        }
+    }
+}
+
+/// A snapshot of a [`Spirit`](crate::spirit::Spirit).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpiritSnapshot {
+    pub id: String,
+    pub position: Position,
+    pub size: u32,
+    pub energy: i32,
+    pub energy_capacity: i32,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub last_energized: Option<String>,
+    pub hp: i32,
+    #[serde(default)]
+    pub sight: SightSnapshot,
+    pub player_id: String,
+    pub shape: Shape,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub merged: Vec<String>,
+    pub move_speed: f64,
+    #[serde(default)]
+    pub mark: String,
+    /// Whether the spirit belongs to you. Filled in from [`Snapshot::this_player_id`].
+    #[serde(skip)]
+    pub friendly: bool,
+}
+
+impl SpiritSnapshot {
+    /// Whether the spirit has positive HP (see [`LivingSpirit`](crate::spirit::LivingSpirit)).
+    #[inline]
+    pub fn is_living(&self) -> bool {
+        self.hp > 0
+    }
+
+    /// Whether the spirit has zero HP (see [`DeadSpirit`](crate::spirit::DeadSpirit)).
+    #[inline]
+    pub fn is_dead(&self) -> bool {
+        !self.is_living()
+    }
+
+    /// Whether the spirit can be given commands (see [`OperableSpirit`](crate::spirit::OperableSpirit)).
+    #[inline]
+    pub fn is_operable(&self) -> bool {
+        self.friendly && self.is_living()
+    }
+}
+
+impl From<&Spirit> for SpiritSnapshot {
+    fn from(spirit: &Spirit) -> Self {
+        let player_id = read_id(&spirit.player_id());
+        SpiritSnapshot {
+            id: read_id(&spirit.id()),
+            position: spirit.position(),
+            size: spirit.size(),
+            energy: spirit.energy(),
+            energy_capacity: spirit.energy_capacity(),
+            last_energized: spirit.last_energized().map(|id| read_id(&id)),
+            hp: spirit.hp(),
+            sight: SightSnapshot::from(&spirit.sight()),
+            friendly: player_id == read_id(&this_player_id),
+            player_id,
+            shape: spirit.shape(),
+            color: spirit.color(),
+            merged: read_ids(spirit.merged()),
+            move_speed: spirit.move_speed(),
+            mark: spirit.mark(),
+        }
+    }
+}
+
+/// A snapshot of a [`Base`](crate::base::Base).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BaseSnapshot {
+    pub id: String,
+    pub position: Position,
+    pub size: u32,
+    pub energy: i32,
+    pub energy_capacity: i32,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub last_energized: Option<String>,
+    pub hp: i32,
+    #[serde(default)]
+    pub sight: SightSnapshot,
+    pub player_id: String,
+    pub shape: Shape,
+    #[serde(default)]
+    pub color: String,
+    pub current_spirit_cost: i32,
+    pub collision_radius: f64,
+    /// Whether the base belongs to you. Filled in from [`Snapshot::this_player_id`].
+    #[serde(skip)]
+    pub friendly: bool,
+}
+
+impl BaseSnapshot {
+    /// Whether the base has positive HP (see [`LivingDestructible`](crate::LivingDestructible)).
+    #[inline]
+    pub fn is_living(&self) -> bool {
+        self.hp > 0
+    }
+}
+
+impl From<&Base> for BaseSnapshot {
+    fn from(base: &Base) -> Self {
+        let destructible: &LivingDestructible = base.as_ref();
+        let player_id = read_id(&destructible.player_id());
+        BaseSnapshot {
+            id: read_id(&base.id()),
+            position: base.position(),
+            size: base.size(),
+            energy: base.energy(),
+            energy_capacity: base.energy_capacity(),
+            last_energized: base.last_energized().map(|id| read_id(&id)),
+            hp: destructible.hp(),
+            sight: SightSnapshot::from(&destructible.sight()),
+            friendly: player_id == read_id(&this_player_id),
+            player_id,
+            shape: destructible.shape(),
+            color: destructible.color(),
+            current_spirit_cost: base.current_spirit_cost(),
+            collision_radius: base.collision_radius(),
+        }
+    }
+}
+
+/// A snapshot of an [`Outpost`](crate::outpost::Outpost).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutpostSnapshot {
+    pub id: String,
+    pub position: Position,
+    pub size: u32,
+    pub energy: i32,
+    pub energy_capacity: i32,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub last_energized: Option<String>,
+    pub range: f64,
+    #[serde(default)]
+    pub sight: OutpostSightSnapshot,
+    /// The ID of the controlling player, or an empty string if the outpost is neutral.
+    #[serde(default)]
+    pub control: String,
+    pub collision_radius: f64,
+}
+
+impl From<&Outpost> for OutpostSnapshot {
+    fn from(outpost: &Outpost) -> Self {
+        OutpostSnapshot {
+            id: read_id(&outpost.id()),
+            position: outpost.position(),
+            size: outpost.size(),
+            energy: outpost.energy(),
+            energy_capacity: outpost.energy_capacity(),
+            last_energized: outpost.last_energized().map(|id| read_id(&id)),
+            range: outpost.range(),
+            sight: OutpostSightSnapshot::from(&outpost.sight()),
+            control: read_id(&outpost.control()),
+            collision_radius: outpost.collision_radius(),
+        }
+    }
+}
+
+/// A snapshot of a [`Star`](crate::star::Star).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StarSnapshot {
+    pub id: String,
+    pub position: Position,
+    pub size: u32,
+    pub energy: i32,
+    pub energy_capacity: i32,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub last_energized: Option<String>,
+    #[serde(default)]
+    pub active_in: u32,
+    #[serde(default)]
+    pub active_at: u32,
+    pub collision_radius: f64,
+}
+
+impl From<&Star> for StarSnapshot {
+    fn from(star: &Star) -> Self {
+        StarSnapshot {
+            id: read_id(&star.id()),
+            position: star.position(),
+            size: star.size(),
+            energy: star.energy(),
+            energy_capacity: star.energy_capacity(),
+            last_energized: star.last_energized().map(|id| read_id(&id)),
+            active_in: star.active_in(),
+            active_at: star.active_at(),
+            collision_radius: star.collision_radius(),
+        }
+    }
+}
+
+/// A snapshot of [`players`](crate::players::players).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayersSnapshot {
+    pub p1: String,
+    pub p2: String,
+}
+
+/// The whole visible game state at a given tick.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u32,
+    pub this_player_id: String,
+    pub players: PlayersSnapshot,
+    pub spirits: BTreeMap<String, SpiritSnapshot>,
+    pub bases: BTreeMap<String, BaseSnapshot>,
+    pub outposts: BTreeMap<String, OutpostSnapshot>,
+    pub stars: BTreeMap<String, StarSnapshot>,
+}
+
+impl Snapshot {
+    /// Copies the current game state out of JS.
+    ///
+    /// The state is transferred as a single JSON string. Fails if JS can't serialize it, or if it doesn't match the
+    /// snapshot structs, say because the game renamed or removed a property they read. Properties the structs
+    /// don't know about are ignored.
+    pub fn capture() -> serde_json::Result<Self> {
+        let state = Object::new();
+        let entries: [(&str, &JsValue); 7] = [
+            ("tick", &JsValue::from(current_tick())),
+            ("this_player_id", this_player_id.as_ref()),
+            ("players", players.as_ref()),
+            ("spirits", spirits.as_ref()),
+            ("bases", bases.as_ref()),
+            ("outposts", outposts.as_ref()),
+            ("stars", stars.as_ref()),
+        ];
+        for (key, value) in entries.iter() {
+            Reflect::set(&state, &JsValue::from_str(key), value).map_err(js_error)?;
+        }
+        let json = String::from(JSON::stringify(&state).map_err(js_error)?);
+        Snapshot::from_json(&json)
+    }
+
+    /// Parses a snapshot from JSON, in the format produced by `JSON.stringify` on the game objects
+    /// (or by serializing a [`Snapshot`]).
+    /// Useful to build fixtures outside of the game.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let mut snapshot: Snapshot = serde_json::from_str(json)?;
+        snapshot.classify();
+        Ok(snapshot)
+    }
+
+    /// Recomputes the [`friendly`](SpiritSnapshot::friendly) flags of spirits and bases
+    /// from [`this_player_id`](Snapshot::this_player_id).
+    /// Call this after modifying player IDs by hand.
+    pub fn classify(&mut self) {
+        let me = &self.this_player_id;
+        for spirit in self.spirits.values_mut() {
+            spirit.friendly = &spirit.player_id == me;
+        }
+        for base in self.bases.values_mut() {
+            base.friendly = &base.player_id == me;
+        }
+    }
+
+    /// Your spirits, dead or alive (like [`FriendlySpirit`](crate::spirit::FriendlySpirit)).
+    pub fn friendly_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.spirits.values().filter(|spirit| spirit.friendly)
+    }
+
+    /// Spirits that aren't yours, dead or alive (like [`EnemySpirit`](crate::spirit::EnemySpirit)).
+    pub fn enemy_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.spirits.values().filter(|spirit| !spirit.friendly)
+    }
+
+    /// Your living spirits (like [`OperableSpirit`](crate::spirit::OperableSpirit)).
+    pub fn operable_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.friendly_spirits().filter(|spirit| spirit.is_living())
+    }
+
+    /// Your dead spirits (like [`DeadFriendlySpirit`](crate::spirit::DeadFriendlySpirit)).
+    pub fn dead_friendly_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.friendly_spirits().filter(|spirit| spirit.is_dead())
+    }
+
+    /// Living enemy spirits (like [`LivingEnemySpirit`](crate::spirit::LivingEnemySpirit)).
+    pub fn living_enemy_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.enemy_spirits().filter(|spirit| spirit.is_living())
+    }
+
+    /// Dead enemy spirits (like [`DeadEnemySpirit`](crate::spirit::DeadEnemySpirit)).
+    pub fn dead_enemy_spirits(&self) -> impl Iterator<Item = &SpiritSnapshot> {
+        self.enemy_spirits().filter(|spirit| spirit.is_dead())
+    }
+
+    /// Your base (like [`base`](crate::base::base)).
+    pub fn base(&self) -> Option<&BaseSnapshot> {
+        self.bases.values().find(|base| base.friendly)
+    }
+
+    /// The enemy base (like [`enemy_base`](crate::base::enemy_base)).
+    pub fn enemy_base(&self) -> Option<&BaseSnapshot> {
+        self.bases.values().find(|base| !base.friendly)
+    }
+
+    /// The position of any entity, looked up by ID.
+    pub fn position_of(&self, id: &str) -> Option<Position> {
+        self.spirits
+            .get(id)
+            .map(|spirit| spirit.position)
+            .or_else(|| self.bases.get(id).map(|base| base.position))
+            .or_else(|| self.outposts.get(id).map(|outpost| outpost.position))
+            .or_else(|| self.stars.get(id).map(|star| star.position))
+    }
+}
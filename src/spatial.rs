@@ -0,0 +1,420 @@
+//! A uniform grid of entity positions, for answering "what's near here" without looping over every entity.
+//!
+//! Build a [`SpatialIndex`] once per tick from any [`GameView`], whether that's [`Live`](crate::game::Live) state
+//! or a [`Snapshot`](crate::snapshot::Snapshot), then query it as often as needed.
+//!
+//! Distances to structures are measured to their edge, using their collision radius, so a spirit touching a
+//! star is at distance 0 from it. The exception is [`enemies_in_energize_range`](SpatialIndex::enemies_in_energize_range),
+//! which follows the game's energize rule: everywhere else in the crate, from the `try_*` methods to the
+//! simulator, energize and harvest ranges are measured between centers.
+
+use crate::game::GameView;
+use crate::rules::rules;
+use crate::Position;
+use std::collections::HashMap;
+
+/// The default [`SpatialIndex`] cell size: the [energize range](crate::rules::Rules::energize_range) under the
+/// current [`rules`], so range queries look at few cells.
+#[inline]
+pub fn default_cell_size() -> f64 {
+    rules().energize_range
+}
+
+/// What kind of entity an [`IndexedEntity`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    Spirit,
+    Base,
+    Outpost,
+    Star,
+}
+
+/// An entry of a [`SpatialIndex`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedEntity {
+    pub id: String,
+    pub kind: EntityKind,
+    pub position: Position,
+    /// 0 for spirits.
+    pub collision_radius: f64,
+    /// The owner's player ID: `player_id` for spirits and bases, `control` for outposts, empty for stars.
+    pub owner: String,
+    /// Stars and outposts are always living.
+    pub living: bool,
+}
+
+impl IndexedEntity {
+    /// The distance from `pos` to the entity's edge.
+    #[inline]
+    pub fn distance_to(&self, pos: Position) -> f64 {
+        (self.position.distance(pos) - self.collision_radius).max(0.0)
+    }
+}
+
+/// A uniform grid of entities.
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    this_player_id: String,
+    cell_size: f64,
+    entities: Vec<IndexedEntity>,
+    by_id: HashMap<String, usize>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// The lowest and highest cell coordinates ever occupied.
+    bounds: ((i32, i32), (i32, i32)),
+    max_radius: f64,
+}
+
+impl SpatialIndex {
+    /// An empty index with cells `cell_size` wide. Entities owned by `this_player_id` are friendly.
+    pub fn new(this_player_id: &str, cell_size: f64) -> Self {
+        SpatialIndex {
+            this_player_id: this_player_id.to_owned(),
+            cell_size,
+            entities: Vec::new(),
+            by_id: HashMap::new(),
+            cells: HashMap::new(),
+            bounds: ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN)),
+            max_radius: 0.0,
+        }
+    }
+
+    /// Indexes every spirit, base, outpost and star in `view`.
+    pub fn from_view(view: &(impl GameView + ?Sized)) -> Self {
+        let mut index = SpatialIndex::new(&view.this_player_id(), default_cell_size());
+        for spirit in view.spirits() {
+            index.insert(IndexedEntity {
+                id: spirit.id.clone(),
+                kind: EntityKind::Spirit,
+                position: spirit.position,
+                collision_radius: 0.0,
+                owner: spirit.player_id.clone(),
+                living: spirit.is_living(),
+            });
+        }
+        for base in view.bases() {
+            index.insert(IndexedEntity {
+                id: base.id.clone(),
+                kind: EntityKind::Base,
+                position: base.position,
+                collision_radius: base.collision_radius,
+                owner: base.player_id.clone(),
+                living: base.is_living(),
+            });
+        }
+        for outpost in view.outposts() {
+            index.insert(IndexedEntity {
+                id: outpost.id.clone(),
+                kind: EntityKind::Outpost,
+                position: outpost.position,
+                collision_radius: outpost.collision_radius,
+                owner: outpost.control.clone(),
+                living: true,
+            });
+        }
+        for star in view.stars() {
+            index.insert(IndexedEntity {
+                id: star.id.clone(),
+                kind: EntityKind::Star,
+                position: star.position,
+                collision_radius: star.collision_radius,
+                owner: String::new(),
+                living: true,
+            });
+        }
+        index
+    }
+
+    #[inline]
+    fn cell(&self, pos: Position) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Adds an entity, replacing any entity with the same ID.
+    pub fn insert(&mut self, entity: IndexedEntity) {
+        self.remove(&entity.id);
+        let index = self.entities.len();
+        let cell = self.cell(entity.position);
+        self.cells.entry(cell).or_default().push(index);
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds;
+        self.bounds = (
+            (min_x.min(cell.0), min_y.min(cell.1)),
+            (max_x.max(cell.0), max_y.max(cell.1)),
+        );
+        self.max_radius = self.max_radius.max(entity.collision_radius);
+        self.by_id.insert(entity.id.clone(), index);
+        self.entities.push(entity);
+    }
+
+    /// Removes an entity. Its memory is only freed when the index is rebuilt.
+    pub fn remove(&mut self, id: &str) -> Option<IndexedEntity> {
+        let index = self.by_id.remove(id)?;
+        let cell = self.cell(self.entities[index].position);
+        if let Some(cell) = self.cells.get_mut(&cell) {
+            cell.retain(|&i| i != index);
+        }
+        Some(self.entities[index].clone())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&IndexedEntity> {
+        self.by_id.get(id).map(|&index| &self.entities[index])
+    }
+
+    /// Whether an entity belongs to [`this_player_id`](crate::players::this_player_id).
+    #[inline]
+    pub fn is_friendly(&self, entity: &IndexedEntity) -> bool {
+        entity.owner == self.this_player_id
+    }
+
+    /// Whether an entity belongs to the other player.
+    #[inline]
+    pub fn is_enemy(&self, entity: &IndexedEntity) -> bool {
+        !entity.owner.is_empty() && entity.owner != self.this_player_id
+    }
+
+    /// All entities whose edge is within `radius` of `center`, in no particular order.
+    pub fn within(&self, center: Position, radius: f64) -> impl Iterator<Item = &IndexedEntity> {
+        let reach = radius + self.max_radius;
+        let (min_x, min_y) = self.cell(center - Position::new(reach, reach));
+        let (max_x, max_y) = self.cell(center + Position::new(reach, reach));
+        // Don't walk empty cells outside the occupied area.
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds;
+        let (min_x, min_y) = (min_x.max(low_x), min_y.max(low_y));
+        let (max_x, max_y) = (max_x.min(high_x), max_y.min(high_y));
+        (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .map(move |&index| &self.entities[index])
+            .filter(move |entity| entity.distance_to(center) <= radius)
+    }
+
+    /// The `k` entities for which `filter` returns `true` that are closest to `center`, closest first.
+    pub fn nearest(
+        &self,
+        center: Position,
+        k: usize,
+        mut filter: impl FnMut(&IndexedEntity) -> bool,
+    ) -> Vec<&IndexedEntity> {
+        if k == 0 || self.cells.is_empty() {
+            return Vec::new();
+        }
+        let (cx, cy) = self.cell(center);
+        // The furthest ring of cells that can contain anything.
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds;
+        let max_ring = (cx - low_x)
+            .max(high_x - cx)
+            .max(cy - low_y)
+            .max(high_y - cy)
+            .max(0);
+        let mut found: Vec<(f64, &IndexedEntity)> = Vec::new();
+        for ring in 0..=max_ring {
+            for x in cx - ring..=cx + ring {
+                for y in cy - ring..=cy + ring {
+                    if (x - cx).abs() != ring && (y - cy).abs() != ring {
+                        continue;
+                    }
+                    for &index in self.cells.get(&(x, y)).into_iter().flatten() {
+                        let entity = &self.entities[index];
+                        if filter(entity) {
+                            found.push((entity.distance_to(center), entity));
+                        }
+                    }
+                }
+            }
+            if found.len() >= k {
+                found.sort_by(|a, b| a.0.total_cmp(&b.0));
+                // Cells beyond this ring are at least this far away, less the largest collision radius.
+                let unexplored = f64::from(ring) * self.cell_size - self.max_radius;
+                if found[k - 1].0 <= unexplored {
+                    break;
+                }
+            }
+        }
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found
+            .into_iter()
+            .take(k)
+            .map(|(_, entity)| entity)
+            .collect()
+    }
+
+    /// Living enemy spirits, bases and outposts within `range` of `pos`.
+    pub fn enemies_within(
+        &self,
+        pos: Position,
+        range: f64,
+    ) -> impl Iterator<Item = &IndexedEntity> {
+        self.within(pos, range)
+            .filter(move |entity| entity.living && self.is_enemy(entity))
+    }
+
+    /// Living enemy spirits, bases and outposts that a spirit at `pos` can energize.
+    ///
+    /// Like the game, this measures the energize range to the target's center, not its edge.
+    #[inline]
+    pub fn enemies_in_energize_range(&self, pos: Position) -> impl Iterator<Item = &IndexedEntity> {
+        let range = rules().energize_range;
+        self.enemies_within(pos, range)
+            .filter(move |entity| entity.position.distance(pos) <= range)
+    }
+
+    /// Living friendly spirits within `range` of `pos`.
+    pub fn friendly_spirits_within(
+        &self,
+        pos: Position,
+        range: f64,
+    ) -> impl Iterator<Item = &IndexedEntity> {
+        self.within(pos, range).filter(move |entity| {
+            entity.kind == EntityKind::Spirit && entity.living && self.is_friendly(entity)
+        })
+    }
+
+    /// The star closest to `pos`.
+    pub fn nearest_star(&self, pos: Position) -> Option<&IndexedEntity> {
+        self.nearest(pos, 1, |entity| entity.kind == EntityKind::Star)
+            .into_iter()
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(id: &str, kind: EntityKind, x: f64, y: f64, owner: &str) -> IndexedEntity {
+        IndexedEntity {
+            id: id.to_owned(),
+            kind,
+            position: Position::new(x, y),
+            collision_radius: if kind == EntityKind::Spirit {
+                0.0
+            } else {
+                50.0
+            },
+            owner: owner.to_owned(),
+            living: true,
+        }
+    }
+
+    fn index() -> SpatialIndex {
+        let mut index = SpatialIndex::new("p1", 100.0);
+        index.insert(entity("p1_1", EntityKind::Spirit, 0.0, 0.0, "p1"));
+        index.insert(entity("p1_2", EntityKind::Spirit, 150.0, 0.0, "p1"));
+        index.insert(entity("p2_1", EntityKind::Spirit, -250.0, 10.0, "p2"));
+        index.insert(entity("star_zxq", EntityKind::Star, 0.0, 400.0, ""));
+        index.insert(entity("base_p2", EntityKind::Base, 1000.0, -1000.0, "p2"));
+        index
+    }
+
+    fn ids<'a>(entities: impl IntoIterator<Item = &'a IndexedEntity>) -> Vec<&'a str> {
+        let mut ids: Vec<_> = entities
+            .into_iter()
+            .map(|entity| entity.id.as_str())
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn within_measures_to_the_edge() {
+        let index = index();
+        assert_eq!(
+            ids(index.within(Position::new(0.0, 0.0), 150.0)),
+            ["p1_1", "p1_2"]
+        );
+        assert_eq!(ids(index.within(Position::new(0.0, 0.0), 149.0)), ["p1_1"]);
+        // The star's center is 400 away, its edge 350.
+        assert_eq!(
+            ids(index.within(Position::new(0.0, 0.0), 350.0)),
+            ["p1_1", "p1_2", "p2_1", "star_zxq"]
+        );
+        assert!(index
+            .within(Position::new(5000.0, 5000.0), 100.0)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn nearest_is_sorted_and_respects_the_filter() {
+        let index = index();
+        let nearest = index.nearest(Position::new(10.0, 0.0), 3, |_| true);
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|entity| entity.id.as_str())
+                .collect::<Vec<_>>(),
+            ["p1_1", "p1_2", "p2_1"]
+        );
+        let enemy = index.nearest(Position::new(10.0, 0.0), 1, |entity| index.is_enemy(entity));
+        assert_eq!(enemy[0].id, "p2_1");
+        assert_eq!(
+            index.nearest(Position::new(0.0, 0.0), 10, |_| true).len(),
+            5
+        );
+        assert!(index
+            .nearest(Position::new(0.0, 0.0), 0, |_| true)
+            .is_empty());
+    }
+
+    #[test]
+    fn nearest_finds_entities_far_outside_the_occupied_cells() {
+        let index = index();
+        let star = index.nearest_star(Position::new(-3000.0, 3000.0)).unwrap();
+        assert_eq!(star.id, "star_zxq");
+    }
+
+    #[test]
+    fn removed_entities_are_not_found() {
+        let mut index = index();
+        assert_eq!(index.remove("p1_2").unwrap().id, "p1_2");
+        assert_eq!(index.remove("p1_2"), None);
+        assert_eq!(index.len(), 4);
+        assert!(index.get("p1_2").is_none());
+        assert_eq!(
+            ids(index.within(Position::new(150.0, 0.0), 10.0)),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            index.nearest(Position::new(150.0, 0.0), 1, |_| true)[0].id,
+            "p1_1"
+        );
+    }
+
+    #[test]
+    fn insert_replaces_an_entity_with_the_same_id() {
+        let mut index = index();
+        index.insert(entity("p1_1", EntityKind::Spirit, 900.0, 900.0, "p1"));
+        assert_eq!(index.len(), 5);
+        assert!(index.within(Position::new(0.0, 0.0), 10.0).next().is_none());
+        assert_eq!(
+            ids(index.within(Position::new(900.0, 900.0), 10.0)),
+            ["p1_1"]
+        );
+    }
+
+    #[test]
+    fn energize_range_is_measured_to_the_center() {
+        let mut index = SpatialIndex::new("p1", default_cell_size());
+        let range = rules().energize_range;
+        // The base's edge is in range, but its center isn't.
+        index.insert(entity("base_p2", EntityKind::Base, range + 10.0, 0.0, "p2"));
+        index.insert(entity("p2_1", EntityKind::Spirit, 0.0, range, "p2"));
+        assert_eq!(
+            ids(index.enemies_in_energize_range(Position::new(0.0, 0.0))),
+            ["p2_1"]
+        );
+    }
+}
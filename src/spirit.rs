@@ -1,10 +1,12 @@
 //! Provides access to spirits.
 
 use crate::{
-    prelude::*, CanFrom, Destructible, DestructibleID, LivingDestructible, LivingDestructibleID,
+    commands::CommandSlot, prelude::*, reject_property, require_shape, rules::rules,
+    ArrayTypedIter, CanFrom, ConversionErrorKind, Destructible, DestructibleID, LivingDestructible,
+    LivingDestructibleID, LivingEntity,
 };
-use js_sys::{Array, Float64Array, Object};
-use std::convert::TryFrom;
+use js_sys::{Array, Float64Array, Function, Object};
+use std::{cell::RefCell, collections::HashSet, convert::TryFrom, error::Error, fmt, ops::Deref};
 use wasm_bindgen::prelude::*;
 
 // Spirit
@@ -37,12 +39,12 @@ extern "C" {
 
 impl CanFrom<Destructible> for Spirit {
     #[inline]
-    fn can_from(value: &Destructible) -> bool {
-        !<Base as CanFrom<Destructible>>::can_from(value)
+    fn check(value: &Destructible) -> Result<(), ConversionErrorKind> {
+        reject_property(value, "current_spirit_cost")
     }
 }
 
-try_can_from!(impl TryFrom<Destructible>, Error = Base for Spirit);
+try_can_from!(impl TryFrom<Destructible> for Spirit);
 
 // FriendlySpirit
 #[wasm_bindgen]
@@ -68,12 +70,16 @@ extern "C" {
 
 impl CanFrom<Spirit> for FriendlySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        &value.player_id() == this_player_id.as_ref()
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        if &value.player_id() == this_player_id.as_ref() {
+            Ok(())
+        } else {
+            Err(ConversionErrorKind::Enemy)
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = EnemySpirit for FriendlySpirit);
+try_can_from!(impl TryFrom<Spirit> for FriendlySpirit);
 
 // EnemySpirit
 #[wasm_bindgen]
@@ -99,12 +105,16 @@ extern "C" {
 
 impl CanFrom<Spirit> for EnemySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        !<FriendlySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        if <FriendlySpirit as CanFrom<Spirit>>::can_from(value) {
+            Err(ConversionErrorKind::Friendly)
+        } else {
+            Ok(())
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = FriendlySpirit for EnemySpirit);
+try_can_from!(impl TryFrom<Spirit> for EnemySpirit);
 
 // LivingSpirit
 #[wasm_bindgen]
@@ -127,21 +137,25 @@ extern "C" {
 
 impl CanFrom<Spirit> for LivingSpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        value.hp() > 0
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        if value.hp() > 0 {
+            Ok(())
+        } else {
+            Err(ConversionErrorKind::Dead)
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = DeadSpirit for LivingSpirit);
+try_can_from!(impl TryFrom<Spirit> for LivingSpirit);
 
 impl CanFrom<LivingDestructible> for LivingSpirit {
     #[inline]
-    fn can_from(value: &LivingDestructible) -> bool {
-        !<Base as CanFrom<LivingDestructible>>::can_from(value)
+    fn check(value: &LivingDestructible) -> Result<(), ConversionErrorKind> {
+        reject_property(value, "current_spirit_cost")
     }
 }
 
-try_can_from!(impl TryFrom<LivingDestructible>, Error = Base for LivingSpirit);
+try_can_from!(impl TryFrom<LivingDestructible> for LivingSpirit);
 
 // DeadSpirit
 #[wasm_bindgen]
@@ -164,12 +178,16 @@ extern "C" {
 
 impl CanFrom<Spirit> for DeadSpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        !<LivingSpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        if <LivingSpirit as CanFrom<Spirit>>::can_from(value) {
+            Err(ConversionErrorKind::Alive)
+        } else {
+            Ok(())
+        }
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = LivingSpirit for DeadSpirit);
+try_can_from!(impl TryFrom<Spirit> for DeadSpirit);
 
 // OperableSpirit
 #[cfg(any(
@@ -209,7 +227,7 @@ extern "C" {
 ))]
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(extends = FriendlySpirit, extends = LivingSpirit, typescript_type = "SquareSpirit")]
+    #[wasm_bindgen(extends = FriendlySpirit, extends = LivingSpirit, typescript_type = "TriangleSpirit")]
     #[derive(Clone, Debug, PartialEq, Eq)]
     pub type OperableSpirit;
 }
@@ -221,9 +239,6 @@ extern "C" {
     #[derive(Clone, Debug, PartialEq, Eq)]
     pub type OperableSpiritID;
 
-    #[wasm_bindgen(method, js_name = "move")]
-    fn r#move(this: &OperableSpirit, target: &Array);
-
     #[wasm_bindgen(method)]
     pub fn energize(this: &OperableSpirit, target: &Entity);
 
@@ -237,15 +252,10 @@ extern "C" {
     #[wasm_bindgen(method)]
     pub fn divide(this: &OperableSpirit);
 
-    /// Requires the `"squares"` crate feature
-    #[cfg(feature = "squares")]
-    #[wasm_bindgen(method, js_name = "jump")]
-    fn _jump(this: &OperableSpirit, target: Array);
-
     /// Requires the `"triangles"` crate feature
     #[cfg(feature = "triangles")]
     #[wasm_bindgen(method)]
-    pub fn explode(this: &OperableSpirit, target: Position);
+    pub fn explode(this: &OperableSpirit);
 
     #[wasm_bindgen(method)]
     pub fn shout(this: &OperableSpirit, message: &str);
@@ -260,42 +270,276 @@ pub type LivingFriendlySpiritID = OperableSpiritID;
 impl OperableSpirit {
     /// `move` method
     pub fn move_to_pos(&self, pos: Position) {
-        self.r#move(&pos.into());
+        crate::shim::r#move(self, pos);
     }
 
+    /// Requires the `"squares"` crate feature
     #[cfg(feature = "squares")]
     pub fn jump(&self, pos: Position) {
-        self._jump(pos.into());
+        crate::shim::jump(self, pos);
+    }
+
+    /// [`energize`](OperableSpirit::energize), if the game rules allow it.
+    /// Energizing yourself harvests from a star in range.
+    pub fn try_energize(&self, target: &Entity) -> Result<(), CommandError> {
+        self.check_operable()?;
+        let this: &JsValue = self.as_ref();
+        let other: &JsValue = target.as_ref();
+        if this == other {
+            if self.energy() >= self.energy_capacity() {
+                return Err(CommandError::Full);
+            }
+            self.check_star_in_range()?;
+        } else {
+            if self.energy() <= 0 {
+                return Err(CommandError::NoEnergy);
+            }
+            if !<LivingEntity as CanFrom<Entity>>::can_from(target) {
+                return Err(CommandError::TargetDead);
+            }
+            check_range(self.position(), target.position(), rules().energize_range)?;
+        }
+        self.claim(CommandSlot::Action)?;
+        self.energize(target);
+        Ok(())
+    }
+
+    /// [`merge`](CircleSpirit::merge), if the game rules allow it.
+    pub fn try_merge(&self, target: &Spirit) -> Result<(), CommandError> {
+        self.check_shape(Shape::Circles)?;
+        self.check_operable()?;
+        let this: &JsValue = self.as_ref();
+        let other: &JsValue = target.as_ref();
+        if this == other {
+            return Err(CommandError::TargetSelf);
+        }
+        if !<LivingSpirit as CanFrom<Spirit>>::can_from(target) {
+            return Err(CommandError::TargetDead);
+        }
+        if !<FriendlySpirit as CanFrom<Spirit>>::can_from(target) {
+            return Err(CommandError::TargetEnemy);
+        }
+        let rules = rules();
+        check_range(self.position(), target.position(), rules.merge_range)?;
+        let size = self.size() + target.size();
+        if size > rules.max_merged_size {
+            return Err(CommandError::TooLarge {
+                size,
+                max: rules.max_merged_size,
+            });
+        }
+        self.claim(CommandSlot::Action)?;
+        self.unchecked_ref::<CircleSpirit>().merge(target);
+        Ok(())
+    }
+
+    /// [`jump`](SquareSpirit::jump), if the game rules allow it.
+    pub fn try_jump(&self, pos: Position) -> Result<(), CommandError> {
+        self.check_shape(Shape::Squares)?;
+        self.check_operable()?;
+        let required = rules().jump_cost(self.position().distance(pos));
+        let available = self.energy();
+        if available < required {
+            return Err(CommandError::NotEnoughEnergy {
+                required,
+                available,
+            });
+        }
+        self.claim(CommandSlot::Movement)?;
+        self.unchecked_ref::<SquareSpirit>().jump(pos);
+        Ok(())
+    }
+
+    /// [`explode`](TriangleSpirit::explode), if the game rules allow it.
+    pub fn try_explode(&self) -> Result<(), CommandError> {
+        self.check_shape(Shape::Triangles)?;
+        self.check_operable()?;
+        self.claim(CommandSlot::Action)?;
+        self.unchecked_ref::<TriangleSpirit>().explode();
+        Ok(())
+    }
+
+    fn check_operable(&self) -> Result<(), CommandError> {
+        if self.hp() <= 0 {
+            return Err(CommandError::Dead);
+        }
+        if !<FriendlySpirit as CanFrom<Spirit>>::can_from(self) {
+            return Err(CommandError::NotOwned);
+        }
+        Ok(())
+    }
+
+    fn check_shape(&self, expected: Shape) -> Result<(), CommandError> {
+        let actual = self.shape();
+        if actual != expected {
+            return Err(CommandError::WrongShape { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Checks that there's an active star to harvest from within energize range.
+    fn check_star_in_range(&self) -> Result<(), CommandError> {
+        let position = self.position();
+        let range = rules().energize_range;
+        let mut nearest = f64::INFINITY;
+        let mut inactive = false;
+        for star in stars.values() {
+            let distance = star.position().distance(position);
+            if distance <= range {
+                if star.active_in() == 0 {
+                    return Ok(());
+                }
+                inactive = true;
+            }
+            nearest = nearest.min(distance);
+        }
+        if inactive {
+            Err(CommandError::StarInactive)
+        } else {
+            Err(CommandError::OutOfRange {
+                distance: nearest,
+                range,
+            })
+        }
     }
+
+    /// Records that this spirit has used `slot` this tick, failing if it already has.
+    ///
+    /// Only commands given through the `try_*` methods are recorded: a plain [`energize`](OperableSpirit::energize)
+    /// or [`move_to_pos`](OperableSpirit::move_to_pos) doesn't count, and a later `try_*` call in the same slot
+    /// will still go through.
+    fn claim(&self, slot: CommandSlot) -> Result<(), CommandError> {
+        let id = self.id().as_string().unwrap_or_default();
+        let current = current_tick();
+        CLAIMED.with(|claimed| {
+            let mut claimed = claimed.borrow_mut();
+            if claimed.0 != current {
+                *claimed = (current, HashSet::new());
+            }
+            if claimed.1.insert((id, slot)) {
+                Ok(())
+            } else {
+                Err(CommandError::AlreadyCommanded(slot))
+            }
+        })
+    }
+}
+
+thread_local! {
+    /// The tick, and the spirits and slots used by `try_*` methods during it.
+    static CLAIMED: RefCell<(u32, HashSet<(String, CommandSlot)>)> = RefCell::new((0, HashSet::new()));
 }
 
+#[inline]
+fn check_range(from: Position, to: Position, range: f64) -> Result<(), CommandError> {
+    let distance = from.distance(to);
+    if distance > range {
+        Err(CommandError::OutOfRange { distance, range })
+    } else {
+        Ok(())
+    }
+}
+
+/// Why a `try_*` method of [`OperableSpirit`] refused to give a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+    /// The spirit is dead.
+    Dead,
+    /// The spirit doesn't belong to you.
+    NotOwned,
+    /// The command is only available to spirits of another shape.
+    WrongShape { expected: Shape, actual: Shape },
+    /// The spirit has no energy to give.
+    NoEnergy,
+    /// The command costs more energy than the spirit has.
+    NotEnoughEnergy { required: i32, available: i32 },
+    /// The spirit is already at its energy capacity, so it can't harvest.
+    Full,
+    /// The star in range isn't active yet, so it can't be harvested.
+    StarInactive,
+    /// The target is too far away.
+    OutOfRange { distance: f64, range: f64 },
+    /// The target is dead.
+    TargetDead,
+    /// The target belongs to the enemy.
+    TargetEnemy,
+    /// The spirit can't target itself with this command.
+    TargetSelf,
+    /// The merged spirit would be bigger than the game allows.
+    TooLarge { size: u32, max: u32 },
+    /// The spirit was already given a command in this slot this tick.
+    AlreadyCommanded(CommandSlot),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Dead => write!(f, "spirit is dead"),
+            CommandError::NotOwned => write!(f, "spirit doesn't belong to this player"),
+            CommandError::WrongShape { expected, actual } => write!(
+                f,
+                "only {} can do this, but spirit is one of the {}",
+                expected.to_str(),
+                actual.to_str()
+            ),
+            CommandError::NoEnergy => write!(f, "spirit has no energy"),
+            CommandError::NotEnoughEnergy {
+                required,
+                available,
+            } => write!(
+                f,
+                "spirit has {} energy, but {} is required",
+                available, required
+            ),
+            CommandError::Full => write!(f, "spirit is already full of energy"),
+            CommandError::StarInactive => write!(f, "star in range isn't active yet"),
+            CommandError::OutOfRange { distance, range } => {
+                write!(f, "target is {:.1} away, but range is {}", distance, range)
+            }
+            CommandError::TargetDead => write!(f, "target is dead"),
+            CommandError::TargetEnemy => write!(f, "target belongs to the enemy"),
+            CommandError::TargetSelf => write!(f, "spirit can't target itself"),
+            CommandError::TooLarge { size, max } => write!(
+                f,
+                "merged spirit would have size {}, but the maximum is {}",
+                size, max
+            ),
+            CommandError::AlreadyCommanded(slot) => {
+                write!(f, "spirit already has a {:?} command this tick", slot)
+            }
+        }
+    }
+}
+
+impl Error for CommandError {}
+
 impl CanFrom<LivingSpirit> for LivingFriendlySpirit {
     #[inline]
-    fn can_from(value: &LivingSpirit) -> bool {
-        <FriendlySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &LivingSpirit) -> Result<(), ConversionErrorKind> {
+        <FriendlySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<LivingSpirit>, Error = LivingEnemySpirit for LivingFriendlySpirit);
+try_can_from!(impl TryFrom<LivingSpirit> for LivingFriendlySpirit);
 
 impl CanFrom<FriendlySpirit> for LivingFriendlySpirit {
     #[inline]
-    fn can_from(value: &FriendlySpirit) -> bool {
-        <LivingSpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &FriendlySpirit) -> Result<(), ConversionErrorKind> {
+        <LivingSpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<FriendlySpirit>, Error = DeadFriendlySpirit for LivingFriendlySpirit);
+try_can_from!(impl TryFrom<FriendlySpirit> for LivingFriendlySpirit);
 
 impl CanFrom<Spirit> for LivingFriendlySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        <LivingSpirit as CanFrom<Spirit>>::can_from(value)
-            && <FriendlySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <LivingSpirit as CanFrom<Spirit>>::check(value)?;
+        <FriendlySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = Spirit for LivingFriendlySpirit);
+try_can_from!(impl TryFrom<Spirit> for LivingFriendlySpirit);
 
 impl AsRef<Spirit> for LivingFriendlySpirit {
     #[inline]
@@ -304,6 +548,188 @@ impl AsRef<Spirit> for LivingFriendlySpirit {
     }
 }
 
+// CircleSpirit
+#[wasm_bindgen]
+extern "C" {
+    /// An [`OperableSpirit`] of the circles.
+    ///
+    /// Unlike the `"circles"` crate feature, the shape is checked at runtime, so one build can play any shape.
+    ///
+    /// [Yare.io Documentation](https://yare.io/documentation#doc_spirit)
+    #[wasm_bindgen(extends = OperableSpirit, extends = FriendlySpirit, extends = LivingSpirit, typescript_type = "CircleSpirit")]
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub type CircleSpirit;
+
+    #[wasm_bindgen(method)]
+    pub fn merge(this: &CircleSpirit, target: &Spirit);
+
+    #[wasm_bindgen(method)]
+    pub fn divide(this: &CircleSpirit);
+}
+
+impl CanFrom<OperableSpirit> for CircleSpirit {
+    #[inline]
+    fn check(value: &OperableSpirit) -> Result<(), ConversionErrorKind> {
+        require_shape(value, Shape::Circles)
+    }
+}
+
+try_can_from!(impl TryFrom<OperableSpirit> for CircleSpirit);
+
+impl CanFrom<Spirit> for CircleSpirit {
+    #[inline]
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <LivingFriendlySpirit as CanFrom<Spirit>>::check(value)?;
+        require_shape(value, Shape::Circles)
+    }
+}
+
+try_can_from!(impl TryFrom<Spirit> for CircleSpirit);
+
+impl AsRef<Spirit> for CircleSpirit {
+    #[inline]
+    fn as_ref(&self) -> &Spirit {
+        <CircleSpirit as AsRef<FriendlySpirit>>::as_ref(self).as_ref()
+    }
+}
+
+// SquareSpirit
+#[wasm_bindgen]
+extern "C" {
+    /// An [`OperableSpirit`] of the squares.
+    ///
+    /// Unlike the `"squares"` crate feature, the shape is checked at runtime, so one build can play any shape.
+    ///
+    /// [Yare.io Documentation](https://yare.io/documentation#doc_spirit)
+    #[wasm_bindgen(extends = OperableSpirit, extends = FriendlySpirit, extends = LivingSpirit, typescript_type = "SquareSpirit")]
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub type SquareSpirit;
+}
+
+impl SquareSpirit {
+    /// `jump` method
+    #[inline]
+    pub fn jump(&self, target: Position) {
+        crate::shim::jump(self, target);
+    }
+}
+
+impl CanFrom<OperableSpirit> for SquareSpirit {
+    #[inline]
+    fn check(value: &OperableSpirit) -> Result<(), ConversionErrorKind> {
+        require_shape(value, Shape::Squares)
+    }
+}
+
+try_can_from!(impl TryFrom<OperableSpirit> for SquareSpirit);
+
+impl CanFrom<Spirit> for SquareSpirit {
+    #[inline]
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <LivingFriendlySpirit as CanFrom<Spirit>>::check(value)?;
+        require_shape(value, Shape::Squares)
+    }
+}
+
+try_can_from!(impl TryFrom<Spirit> for SquareSpirit);
+
+impl AsRef<Spirit> for SquareSpirit {
+    #[inline]
+    fn as_ref(&self) -> &Spirit {
+        <SquareSpirit as AsRef<FriendlySpirit>>::as_ref(self).as_ref()
+    }
+}
+
+// TriangleSpirit
+#[wasm_bindgen]
+extern "C" {
+    /// An [`OperableSpirit`] of the triangles.
+    ///
+    /// Unlike the `"triangles"` crate feature, the shape is checked at runtime, so one build can play any shape.
+    ///
+    /// [Yare.io Documentation](https://yare.io/documentation#doc_spirit)
+    #[wasm_bindgen(extends = OperableSpirit, extends = FriendlySpirit, extends = LivingSpirit, typescript_type = "TriangleSpirit")]
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub type TriangleSpirit;
+
+    #[wasm_bindgen(method)]
+    pub fn explode(this: &TriangleSpirit);
+}
+
+impl CanFrom<OperableSpirit> for TriangleSpirit {
+    #[inline]
+    fn check(value: &OperableSpirit) -> Result<(), ConversionErrorKind> {
+        require_shape(value, Shape::Triangles)
+    }
+}
+
+try_can_from!(impl TryFrom<OperableSpirit> for TriangleSpirit);
+
+impl CanFrom<Spirit> for TriangleSpirit {
+    #[inline]
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <LivingFriendlySpirit as CanFrom<Spirit>>::check(value)?;
+        require_shape(value, Shape::Triangles)
+    }
+}
+
+try_can_from!(impl TryFrom<Spirit> for TriangleSpirit);
+
+impl AsRef<Spirit> for TriangleSpirit {
+    #[inline]
+    fn as_ref(&self) -> &Spirit {
+        <TriangleSpirit as AsRef<FriendlySpirit>>::as_ref(self).as_ref()
+    }
+}
+
+/// An [`OperableSpirit`], as the type for its [`shape`](Destructible::shape).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypedSpirit {
+    Circle(CircleSpirit),
+    Square(SquareSpirit),
+    Triangle(TriangleSpirit),
+    /// A spirit whose shape this crate doesn't know about.
+    Unknown(OperableSpirit),
+}
+
+impl From<OperableSpirit> for TypedSpirit {
+    #[inline]
+    fn from(spirit: OperableSpirit) -> Self {
+        match spirit.shape() {
+            Shape::Circles => TypedSpirit::Circle(spirit.unchecked_into()),
+            Shape::Squares => TypedSpirit::Square(spirit.unchecked_into()),
+            Shape::Triangles => TypedSpirit::Triangle(spirit.unchecked_into()),
+            _ => TypedSpirit::Unknown(spirit),
+        }
+    }
+}
+
+impl From<TypedSpirit> for OperableSpirit {
+    #[inline]
+    fn from(spirit: TypedSpirit) -> Self {
+        match spirit {
+            TypedSpirit::Circle(circle) => circle.into(),
+            TypedSpirit::Square(square) => square.into(),
+            TypedSpirit::Triangle(triangle) => triangle.into(),
+            TypedSpirit::Unknown(spirit) => spirit,
+        }
+    }
+}
+
+impl Deref for TypedSpirit {
+    type Target = OperableSpirit;
+
+    #[inline]
+    fn deref(&self) -> &OperableSpirit {
+        match self {
+            TypedSpirit::Circle(circle) => circle,
+            TypedSpirit::Square(square) => square,
+            TypedSpirit::Triangle(triangle) => triangle,
+            TypedSpirit::Unknown(spirit) => spirit,
+        }
+    }
+}
+
 // DeadFriendlySpirit
 #[wasm_bindgen]
 extern "C" {
@@ -325,31 +751,31 @@ extern "C" {
 
 impl CanFrom<FriendlySpirit> for DeadFriendlySpirit {
     #[inline]
-    fn can_from(value: &FriendlySpirit) -> bool {
-        <DeadSpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &FriendlySpirit) -> Result<(), ConversionErrorKind> {
+        <DeadSpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<FriendlySpirit>, Error = LivingFriendlySpirit for DeadFriendlySpirit);
+try_can_from!(impl TryFrom<FriendlySpirit> for DeadFriendlySpirit);
 
 impl CanFrom<DeadSpirit> for DeadFriendlySpirit {
     #[inline]
-    fn can_from(value: &DeadSpirit) -> bool {
-        <FriendlySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &DeadSpirit) -> Result<(), ConversionErrorKind> {
+        <FriendlySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<DeadSpirit>, Error = DeadEnemySpirit for DeadFriendlySpirit);
+try_can_from!(impl TryFrom<DeadSpirit> for DeadFriendlySpirit);
 
 impl CanFrom<Spirit> for DeadFriendlySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        <DeadSpirit as CanFrom<Spirit>>::can_from(value)
-            && <FriendlySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <DeadSpirit as CanFrom<Spirit>>::check(value)?;
+        <FriendlySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = Spirit for DeadFriendlySpirit);
+try_can_from!(impl TryFrom<Spirit> for DeadFriendlySpirit);
 
 impl AsRef<Spirit> for DeadFriendlySpirit {
     #[inline]
@@ -379,31 +805,31 @@ extern "C" {
 
 impl CanFrom<EnemySpirit> for LivingEnemySpirit {
     #[inline]
-    fn can_from(value: &EnemySpirit) -> bool {
-        <LivingSpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &EnemySpirit) -> Result<(), ConversionErrorKind> {
+        <LivingSpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<EnemySpirit>, Error = DeadEnemySpirit for LivingEnemySpirit);
+try_can_from!(impl TryFrom<EnemySpirit> for LivingEnemySpirit);
 
 impl CanFrom<LivingSpirit> for LivingEnemySpirit {
     #[inline]
-    fn can_from(value: &LivingSpirit) -> bool {
-        <EnemySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &LivingSpirit) -> Result<(), ConversionErrorKind> {
+        <EnemySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<LivingSpirit>, Error = LivingFriendlySpirit for LivingEnemySpirit);
+try_can_from!(impl TryFrom<LivingSpirit> for LivingEnemySpirit);
 
 impl CanFrom<Spirit> for LivingEnemySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        <LivingSpirit as CanFrom<Spirit>>::can_from(value)
-            && <EnemySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <LivingSpirit as CanFrom<Spirit>>::check(value)?;
+        <EnemySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = Spirit for LivingEnemySpirit);
+try_can_from!(impl TryFrom<Spirit> for LivingEnemySpirit);
 
 impl AsRef<Spirit> for LivingEnemySpirit {
     #[inline]
@@ -433,31 +859,31 @@ extern "C" {
 
 impl CanFrom<EnemySpirit> for DeadEnemySpirit {
     #[inline]
-    fn can_from(value: &EnemySpirit) -> bool {
-        <DeadSpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &EnemySpirit) -> Result<(), ConversionErrorKind> {
+        <DeadSpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<EnemySpirit>, Error = LivingEnemySpirit for DeadEnemySpirit);
+try_can_from!(impl TryFrom<EnemySpirit> for DeadEnemySpirit);
 
 impl CanFrom<DeadSpirit> for DeadEnemySpirit {
     #[inline]
-    fn can_from(value: &DeadSpirit) -> bool {
-        <EnemySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &DeadSpirit) -> Result<(), ConversionErrorKind> {
+        <EnemySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<DeadSpirit>, Error = DeadFriendlySpirit for DeadEnemySpirit);
+try_can_from!(impl TryFrom<DeadSpirit> for DeadEnemySpirit);
 
 impl CanFrom<Spirit> for DeadEnemySpirit {
     #[inline]
-    fn can_from(value: &Spirit) -> bool {
-        <DeadSpirit as CanFrom<Spirit>>::can_from(value)
-            && <EnemySpirit as CanFrom<Spirit>>::can_from(value)
+    fn check(value: &Spirit) -> Result<(), ConversionErrorKind> {
+        <DeadSpirit as CanFrom<Spirit>>::check(value)?;
+        <EnemySpirit as CanFrom<Spirit>>::check(value)
     }
 }
 
-try_can_from!(impl TryFrom<Spirit>, Error = Spirit for DeadEnemySpirit);
+try_can_from!(impl TryFrom<Spirit> for DeadEnemySpirit);
 
 impl AsRef<Spirit> for DeadEnemySpirit {
     #[inline]
@@ -494,10 +920,90 @@ impl GetByID<DeadFriendlySpiritID, DeadFriendlySpirit> for Spirits {}
 impl GetByID<LivingEnemySpiritID, LivingEnemySpirit> for Spirits {}
 impl GetByID<DeadEnemySpiritID, DeadEnemySpirit> for Spirits {}
 
+thread_local! {
+    /// The spirits in `spirits` that are friendly and living, as requested.
+    /// `null` or `undefined` (what `None` becomes) matches either.
+    static FILTER_SPIRITS: Function = Function::new_with_args(
+        "friendly, living",
+        r#"
+        const filtered = [];
+        for (const id in spirits) {
+            const spirit = spirits[id];
+            if (friendly != null && (spirit.player_id === this_player_id) !== friendly) continue;
+            if (living != null && (spirit.hp > 0) !== living) continue;
+            filtered.push(spirit);
+        }
+        return filtered;
+        "#,
+    );
+}
+
+impl Spirits {
+    fn filtered<T: JsCast>(
+        &self,
+        friendly: Option<bool>,
+        living: Option<bool>,
+    ) -> ArrayTypedIter<T> {
+        let array: Array = FILTER_SPIRITS
+            .with(|filter| filter.call2(&JsValue::NULL, &friendly.into(), &living.into()))
+            .unwrap()
+            .unchecked_into();
+        ArrayTypedIter::new(array)
+    }
+
+    /// An iterator over all living spirits.
+    #[inline]
+    pub fn living(&self) -> ArrayTypedIter<LivingSpirit> {
+        self.filtered(None, Some(true))
+    }
+
+    /// An iterator over all dead spirits.
+    #[inline]
+    pub fn dead(&self) -> ArrayTypedIter<DeadSpirit> {
+        self.filtered(None, Some(false))
+    }
+
+    /// An iterator over your spirits, living or dead.
+    #[inline]
+    pub fn friendly(&self) -> ArrayTypedIter<FriendlySpirit> {
+        self.filtered(Some(true), None)
+    }
+
+    /// An iterator over the enemy's spirits, living or dead.
+    #[inline]
+    pub fn enemies(&self) -> ArrayTypedIter<EnemySpirit> {
+        self.filtered(Some(false), None)
+    }
+
+    /// An iterator over your living spirits.
+    #[inline]
+    pub fn operable(&self) -> ArrayTypedIter<OperableSpirit> {
+        self.filtered(Some(true), Some(true))
+    }
+
+    /// An iterator over your dead spirits.
+    #[inline]
+    pub fn dead_friendly(&self) -> ArrayTypedIter<DeadFriendlySpirit> {
+        self.filtered(Some(true), Some(false))
+    }
+
+    /// An iterator over the enemy's living spirits.
+    #[inline]
+    pub fn living_enemies(&self) -> ArrayTypedIter<LivingEnemySpirit> {
+        self.filtered(Some(false), Some(true))
+    }
+
+    /// An iterator over the enemy's dead spirits.
+    #[inline]
+    pub fn dead_enemies(&self) -> ArrayTypedIter<DeadEnemySpirit> {
+        self.filtered(Some(false), Some(false))
+    }
+}
+
 // `my_spirits`
 #[wasm_bindgen]
 extern "C" {
-    /// `my_spirits`, as a [`Vec`].
+    /// `my_spirits`, as a [`Vec`]. Includes dead spirits; use [`Spirits::operable`] for only the living ones.
     ///
     /// [Yare.io Documentation](https://yare.io/documentation#doc_spirit)
     #[wasm_bindgen(method, getter)]
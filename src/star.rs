@@ -1,7 +1,9 @@
 //! Provides access to stars.
 
-use crate::{prelude::*, CanFrom};
-use crate::{Structure, StructureID};
+use crate::rules::rules;
+use crate::snapshot::StarSnapshot;
+use crate::{prelude::*, require_structure_type, CanFrom, ConversionErrorKind};
+use crate::{Structure, StructureID, StructureType};
 use js_sys::Object;
 use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
@@ -33,12 +35,12 @@ extern "C" {
 
 impl CanFrom<Structure> for Star {
     #[inline]
-    fn can_from(value: &Structure) -> bool {
-        value.structure_type().to_str() == "star"
+    fn check(value: &Structure) -> Result<(), ConversionErrorKind> {
+        require_structure_type(value, StructureType::Star)
     }
 }
 
-try_can_from!(impl TryFrom<Structure>, Error = Structure for Star);
+try_can_from!(impl TryFrom<Structure> for Star);
 
 // `stars`
 #[wasm_bindgen]
@@ -88,3 +90,186 @@ extern "C" {
     #[wasm_bindgen]
     pub static star_p89: Star;
 }
+
+// StarModel
+
+/// Predicts how a star's energy changes over time, under the current [`rules`](crate::rules::rules).
+///
+/// Each tick the star is active, harvests are taken first, then the star regenerates.
+///
+/// ```ignore
+/// let model = StarModel::from(&*star_zxq);
+/// // How much can we take each tick without draining it?
+/// let rate = model.sustainable_harvest();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StarModel {
+    pub energy: i32,
+    pub energy_capacity: i32,
+    /// Ticks until the star starts regenerating and can be harvested.
+    pub active_in: u32,
+}
+
+impl StarModel {
+    #[inline]
+    pub fn new(energy: i32, energy_capacity: i32, active_in: u32) -> Self {
+        StarModel {
+            energy,
+            energy_capacity,
+            active_in,
+        }
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active_in == 0
+    }
+
+    /// Advances the model by one tick, with `harvest` energy taken from the star if it's active.
+    /// Returns how much was actually taken.
+    pub fn step(&mut self, harvest: i32) -> i32 {
+        if self.active_in > 0 {
+            self.active_in -= 1;
+            return 0;
+        }
+        let taken = harvest.clamp(0, self.energy.max(0));
+        self.energy -= taken;
+        self.energy = (self.energy + rules().star_regen(self.energy)).min(self.energy_capacity);
+        taken
+    }
+
+    /// The star's energy in `ticks` ticks, if `harvest` energy is taken from it every tick.
+    pub fn predict(&self, ticks: u32, harvest: i32) -> i32 {
+        let mut model = *self;
+        for _ in 0..ticks {
+            model.step(harvest);
+        }
+        model.energy
+    }
+
+    /// How many ticks until the star has at least `energy`, if `harvest` energy is taken from it every tick.
+    /// `None` if it never will.
+    pub fn ticks_until(&self, energy: i32, harvest: i32) -> Option<u32> {
+        if energy > self.energy_capacity {
+            return None;
+        }
+        let mut model = *self;
+        let mut ticks = 0;
+        while model.energy < energy {
+            let before = model.energy;
+            let was_active = model.is_active();
+            model.step(harvest);
+            ticks += 1;
+            // Regeneration grows with energy, so once it stops gaining, it never will.
+            if was_active && model.energy <= before {
+                return None;
+            }
+        }
+        Some(ticks)
+    }
+
+    /// The most energy that can be taken every tick without the star's energy going down, from its current energy.
+    /// 0 while the star isn't active, since nothing can be taken from it.
+    pub fn sustainable_harvest(&self) -> i32 {
+        if !self.is_active() {
+            return 0;
+        }
+        Self::sustainable_harvest_at(self.energy)
+    }
+
+    /// The most energy that can be taken every tick from a full star without its energy going down.
+    /// Unlike [`sustainable_harvest`](StarModel::sustainable_harvest), this ignores
+    /// [`active_in`](StarModel::active_in): it's what the star will sustain once it's active and full.
+    pub fn max_sustainable_harvest(&self) -> i32 {
+        Self::sustainable_harvest_at(self.energy_capacity)
+    }
+
+    fn sustainable_harvest_at(energy: i32) -> i32 {
+        let rules = rules();
+        let mut harvest = 0;
+        while harvest < energy && rules.star_regen(energy - harvest - 1) > harvest {
+            harvest += 1;
+        }
+        harvest
+    }
+}
+
+impl From<&Star> for StarModel {
+    #[inline]
+    fn from(star: &Star) -> Self {
+        StarModel::new(star.energy(), star.energy_capacity(), star.active_in())
+    }
+}
+
+impl From<&StarSnapshot> for StarModel {
+    #[inline]
+    fn from(star: &StarSnapshot) -> Self {
+        StarModel::new(star.energy, star.energy_capacity, star.active_in)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_harvests_then_regenerates() {
+        let mut model = StarModel::new(100, 1000, 0);
+        // 100 - 50 = 50, then 50 regenerates round(3.5) = 4.
+        assert_eq!(model.step(50), 50);
+        assert_eq!(model.energy, 54);
+
+        let mut model = StarModel::new(10, 1000, 0);
+        assert_eq!(model.step(50), 10);
+        assert_eq!(model.energy, 3);
+        assert_eq!(model.step(-5), 0);
+        assert_eq!(model.energy, 6);
+    }
+
+    #[test]
+    fn predict_caps_at_capacity_and_waits_while_inactive() {
+        assert_eq!(StarModel::new(999, 1000, 0).predict(1, 0), 1000);
+        assert_eq!(StarModel::new(100, 1000, 0).predict(0, 50), 100);
+
+        let inactive = StarModel::new(100, 1000, 2);
+        assert_eq!(inactive.predict(2, 50), 100);
+        assert_eq!(inactive.predict(3, 0), 104);
+        assert_eq!(inactive.predict(3, 50), 54);
+    }
+
+    #[test]
+    fn ticks_until() {
+        let model = StarModel::new(100, 1000, 0);
+        assert_eq!(model.ticks_until(100, 0), Some(0));
+        assert_eq!(model.ticks_until(104, 0), Some(1));
+        assert!(matches!(model.ticks_until(1000, 0), Some(ticks) if ticks > 1));
+        assert_eq!(model.ticks_until(1001, 0), None);
+        // Taking exactly what it regenerates keeps it at 100.
+        assert_eq!(model.ticks_until(101, 4), None);
+        assert_eq!(model.ticks_until(200, 50), None);
+
+        assert_eq!(StarModel::new(100, 1000, 3).ticks_until(104, 0), Some(4));
+    }
+
+    #[test]
+    fn sustainable_harvest_keeps_the_energy_steady() {
+        let full = StarModel::new(1000, 1000, 0);
+        let harvest = full.sustainable_harvest();
+        assert_eq!(harvest, 13);
+        assert_eq!(full.predict(50, harvest), 1000);
+        assert!(full.predict(1, harvest + 1) < 1000);
+
+        let low = StarModel::new(100, 1000, 0);
+        let harvest = low.sustainable_harvest();
+        assert!(low.predict(1, harvest) >= 100);
+        assert!(low.predict(1, harvest + 1) < 100);
+    }
+
+    #[test]
+    fn sustainable_harvest_is_zero_while_inactive_or_empty() {
+        let inactive = StarModel::new(1000, 1000, 5);
+        assert_eq!(inactive.sustainable_harvest(), 0);
+        assert_eq!(inactive.max_sustainable_harvest(), 13);
+        assert_eq!(StarModel::new(0, 1000, 0).sustainable_harvest(), 0);
+    }
+}